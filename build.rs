@@ -0,0 +1,51 @@
+//! Generates a test for every answer in `data/answers.toml`.
+
+use std::fmt::Write;
+
+#[path = "src/answers/toml.rs"]
+#[allow(dead_code)]
+mod toml;
+
+fn main() {
+    println!("cargo:rerun-if-changed=data/answers.toml");
+    println!("cargo:rerun-if-changed=src/answers/toml.rs");
+    let text = std::fs::read_to_string("data/answers.toml").expect("Failed to read answers");
+    let tables = toml::parse(&text).unwrap_or_else(|err| {
+        panic!("data/answers.toml:{}: {}", err.line, err.message);
+    });
+
+    let mut tests = String::new();
+    for table in &tables {
+        let slow = table
+            .pairs
+            .iter()
+            .find(|pair| pair.key == "slow")
+            .map(|pair| {
+                toml::slow_parts(&pair.value).unwrap_or_else(|message| {
+                    panic!("data/answers.toml:{}: {}", pair.line, message);
+                })
+            })
+            .unwrap_or_default();
+        for pair in &table.pairs {
+            let Some(part) = pair.key.strip_prefix("part_") else {
+                continue;
+            };
+            let is_slow = slow.iter().any(|slow| slow.to_string() == part);
+            let name = format!("answer_{}_part_{}", table.name.replace('-', "_"), part);
+            let ignore = if is_slow {
+                "#[ignore = \"slow\"]\n"
+            } else {
+                ""
+            };
+            writeln!(
+                tests,
+                "#[test]\n{}fn {}() {{\n    assert_answer({:?}, {});\n}}\n",
+                ignore, name, table.name, part
+            )
+            .unwrap();
+        }
+    }
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(format!("{}/answer_tests.rs", out_dir), tests).unwrap();
+}
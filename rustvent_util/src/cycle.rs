@@ -0,0 +1,98 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use num::PrimInt;
+
+/// Where the states of a simulation start repeating: the state after step `start + len` is
+/// the one after step `start`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub len: usize,
+}
+
+/// Records a key for the state after every step of a simulation, together with a metric
+/// that grows by the same amount every time the states repeat, like a height or a count.
+pub struct Detector<K, M> {
+    seen: HashMap<K, usize>,
+    metrics: Vec<M>,
+}
+
+impl<K: Eq + Hash, M: PrimInt> Detector<K, M> {
+    pub fn new() -> Self {
+        Self {
+            seen: HashMap::new(),
+            metrics: Vec::new(),
+        }
+    }
+
+    /// Records the next state, starting with the one before the first step. Returns the cycle
+    /// as soon as a key repeats.
+    pub fn record(&mut self, key: K, metric: M) -> Option<Cycle> {
+        let step = self.metrics.len();
+        self.metrics.push(metric);
+        match self.seen.entry(key) {
+            Entry::Occupied(entry) => Some(Cycle {
+                start: *entry.get(),
+                len: step - entry.get(),
+            }),
+            Entry::Vacant(entry) => {
+                entry.insert(step);
+                None
+            }
+        }
+    }
+
+    /// The metric after `step` steps, extrapolated along the cycle if it wasn't recorded.
+    pub fn extrapolate(&self, cycle: Cycle, step: usize) -> M {
+        if step < self.metrics.len() {
+            return self.metrics[step];
+        }
+        let growth = self.metrics[cycle.start + cycle.len] - self.metrics[cycle.start];
+        let cycles = (step - cycle.start) / cycle.len;
+        let rest = (step - cycle.start) % cycle.len;
+        let cycles = M::from(cycles).expect("Too many cycles for the metric");
+        self.metrics[cycle.start + rest] + growth * cycles
+    }
+}
+
+impl<K: Eq + Hash, M: PrimInt> Default for Detector<K, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detector() {
+        // 1, 2, 4, 8, 16 mod 10 repeats 2, 4, 8, 6 from step 1 on.
+        let mut detector = Detector::new();
+        let mut cycle = None;
+        let mut x = 1;
+        let mut sum = 0u64;
+        while cycle.is_none() {
+            sum += x;
+            cycle = detector.record(x, sum);
+            x = x * 2 % 10;
+        }
+        let cycle = cycle.unwrap();
+        assert_eq!(cycle, Cycle { start: 1, len: 4 });
+        assert_eq!(detector.extrapolate(cycle, 3), 1 + 2 + 4 + 8);
+        let brute = |steps: usize| {
+            let mut x = 1;
+            let mut sum = 0;
+            for _ in 0..=steps {
+                sum += x;
+                x = x * 2 % 10;
+            }
+            sum
+        };
+        for steps in [5, 6, 7, 8, 100, 1001] {
+            assert_eq!(detector.extrapolate(cycle, steps), brute(steps));
+        }
+    }
+}
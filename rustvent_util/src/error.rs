@@ -0,0 +1,93 @@
+use std::fmt::Display;
+
+/// An error in an input file, located as precisely as the failing parser knows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub message: String,
+    pub token: Option<String>,
+    pub file: Option<String>,
+    /// 1-based, relative to the lines the parser was given until it's shifted.
+    pub line: Option<usize>,
+    /// 1-based.
+    pub column: Option<usize>,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            token: None,
+            file: None,
+            line: None,
+            column: None,
+        }
+    }
+
+    pub fn invalid(message: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            token: Some(token.into()),
+            ..Self::new(message)
+        }
+    }
+
+    pub fn in_file(mut self, file: &str) -> Self {
+        self.file.get_or_insert_with(|| file.to_string());
+        self
+    }
+
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line.get_or_insert(line);
+        self
+    }
+
+    pub fn at_column(mut self, column: usize) -> Self {
+        self.column.get_or_insert(column);
+        self
+    }
+
+    /// Moves a line number relative to a block to the position of the block.
+    pub fn shift_lines(mut self, offset: usize) -> Self {
+        if let Some(line) = &mut self.line {
+            *line += offset;
+        }
+        self
+    }
+
+    /// Guesses the column from the first occurrence of the offending token.
+    pub fn locate_in(self, line: &str) -> Self {
+        let index = match &self.token {
+            Some(token) if !token.is_empty() => line.find(token.as_str()),
+            _ => None,
+        };
+        match index {
+            Some(index) => self.at_column(index + 1),
+            None => self,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        if let Some(line) = self.line {
+            write!(f, "{}:", line)?;
+            if let Some(column) = self.column {
+                write!(f, "{}:", column)?;
+            }
+        }
+        if self.file.is_some() || self.line.is_some() {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(token) = &self.token {
+            write!(f, ": {:?}", token)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
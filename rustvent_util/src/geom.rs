@@ -0,0 +1,289 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use num::{PrimInt, Signed};
+
+/// The component type of vectors: any signed primitive integer.
+pub trait Coord: PrimInt + Signed {}
+
+impl<T: PrimInt + Signed> Coord for T {}
+
+/// A point or a direction in the plane. Grids are read top to bottom, so `y` points down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Vec2<T = i32> {
+    pub x: T,
+    pub y: T,
+}
+
+/// A point or a direction in space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Vec3<T = i32> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+/// What [`Bounds`] needs of a vector type.
+pub trait Vector: Copy + PartialEq + Add<Output = Self> + Sub<Output = Self> {
+    type Coord: Coord;
+
+    /// A vector with every component set to `c`.
+    fn splat(c: Self::Coord) -> Self;
+
+    /// The component-wise minimum.
+    fn min(self, other: Self) -> Self;
+
+    /// The component-wise maximum.
+    fn max(self, other: Self) -> Self;
+}
+
+macro_rules! impl_vector {
+    ($name:ident, $($field:ident),+) => {
+        impl<T: Coord> $name<T> {
+            pub fn new($($field: T),+) -> Self {
+                Self { $($field),+ }
+            }
+
+            pub fn zero() -> Self {
+                Self::splat(T::zero())
+            }
+
+            /// The component-wise signum: a step of at most one along every axis.
+            pub fn signum(self) -> Self {
+                Self { $($field: self.$field.signum()),+ }
+            }
+
+            pub fn abs(self) -> Self {
+                Self { $($field: self.$field.abs()),+ }
+            }
+
+            /// The sum of the distances along every axis.
+            pub fn manhattan(self, other: Self) -> T {
+                let d = (self - other).abs();
+                T::zero() $(+ d.$field)+
+            }
+
+            /// The largest distance along any axis: the number of king moves from one to the
+            /// other.
+            pub fn chebyshev(self, other: Self) -> T {
+                let d = (self - other).abs();
+                T::zero() $(.max(d.$field))+
+            }
+        }
+
+        impl<T: Coord> Vector for $name<T> {
+            type Coord = T;
+
+            fn splat(c: T) -> Self {
+                Self { $($field: c),+ }
+            }
+
+            fn min(self, other: Self) -> Self {
+                Self { $($field: self.$field.min(other.$field)),+ }
+            }
+
+            fn max(self, other: Self) -> Self {
+                Self { $($field: self.$field.max(other.$field)),+ }
+            }
+        }
+
+        impl<T: Coord> Add for $name<T> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self { $($field: self.$field + other.$field),+ }
+            }
+        }
+
+        impl<T: Coord> Sub for $name<T> {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self { $($field: self.$field - other.$field),+ }
+            }
+        }
+
+        impl<T: Coord> Neg for $name<T> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+
+        impl<T: Coord> Mul<T> for $name<T> {
+            type Output = Self;
+
+            fn mul(self, factor: T) -> Self {
+                Self { $($field: self.$field * factor),+ }
+            }
+        }
+
+        impl<T: Coord> AddAssign for $name<T> {
+            fn add_assign(&mut self, other: Self) {
+                *self = *self + other;
+            }
+        }
+
+        impl<T: Coord> SubAssign for $name<T> {
+            fn sub_assign(&mut self, other: Self) {
+                *self = *self - other;
+            }
+        }
+    };
+}
+
+impl_vector!(Vec2, x, y);
+impl_vector!(Vec3, x, y, z);
+
+impl<T: Coord> Vec2<T> {
+    /// Turns by 90 degrees clockwise on screen, e.g. from right to down.
+    pub fn turn_right(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    /// Turns by 90 degrees counterclockwise on screen, e.g. from right to up.
+    pub fn turn_left(self) -> Self {
+        Self::new(self.y, -self.x)
+    }
+
+    /// The horizontal and vertical neighbours: up, left, right, down.
+    pub fn neighbours_4(self) -> [Self; 4] {
+        let (zero, one) = (T::zero(), T::one());
+        [(zero, -one), (-one, zero), (one, zero), (zero, one)].map(|(x, y)| self + Self::new(x, y))
+    }
+
+    /// The horizontal, vertical and diagonal neighbours, row by row.
+    pub fn neighbours_8(self) -> [Self; 8] {
+        let (zero, one) = (T::zero(), T::one());
+        [
+            (-one, -one),
+            (zero, -one),
+            (one, -one),
+            (-one, zero),
+            (one, zero),
+            (-one, one),
+            (zero, one),
+            (one, one),
+        ]
+        .map(|(x, y)| self + Self::new(x, y))
+    }
+}
+
+impl<T: Coord> Vec3<T> {
+    /// The neighbours sharing a face with a unit cube at this point.
+    pub fn neighbours_6(self) -> [Self; 6] {
+        let (zero, one) = (T::zero(), T::one());
+        [
+            (-one, zero, zero),
+            (one, zero, zero),
+            (zero, -one, zero),
+            (zero, one, zero),
+            (zero, zero, -one),
+            (zero, zero, one),
+        ]
+        .map(|(x, y, z)| self + Self::new(x, y, z))
+    }
+}
+
+/// An axis-aligned box, including both corners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds<V> {
+    pub min: V,
+    pub max: V,
+}
+
+impl<V: Vector> Bounds<V> {
+    /// The smallest box containing all points, `None` if there are none.
+    pub fn of(points: impl IntoIterator<Item = V>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |bounds, point| Self {
+                min: bounds.min.min(point),
+                max: bounds.max.max(point),
+            },
+        ))
+    }
+
+    pub fn contains(&self, point: V) -> bool {
+        self.min.min(point) == self.min && self.max.max(point) == self.max
+    }
+
+    /// Widens the box by `margin` on every side.
+    pub fn grow(&self, margin: V::Coord) -> Self {
+        Self {
+            min: self.min - V::splat(margin),
+            max: self.max + V::splat(margin),
+        }
+    }
+
+    /// The number of points along every axis.
+    pub fn size(&self) -> V {
+        self.max - self.min + V::splat(num::one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Vec2::new(3, -4);
+        let b = Vec2::new(-1, 2);
+        assert_eq!(a + b, Vec2::new(2, -2));
+        assert_eq!(a - b, Vec2::new(4, -6));
+        assert_eq!(-a, Vec2::new(-3, 4));
+        assert_eq!(a * 2, Vec2::new(6, -8));
+        assert_eq!(a.signum(), Vec2::new(1, -1));
+        assert_eq!(Vec3::new(0, -7, 2).signum(), Vec3::new(0, -1, 1));
+    }
+
+    #[test]
+    fn test_distances() {
+        let a = Vec2::new(1i64, 1);
+        let b = Vec2::new(4, -3);
+        assert_eq!(a.manhattan(b), 7);
+        assert_eq!(a.chebyshev(b), 4);
+        assert_eq!(Vec3::new(1, 2, 3).manhattan(Vec3::zero()), 6);
+        assert_eq!(Vec3::new(1, -5, 3).chebyshev(Vec3::zero()), 5);
+    }
+
+    #[test]
+    fn test_turns() {
+        let right = Vec2::new(1, 0);
+        let down = Vec2::new(0, 1);
+        assert_eq!(right.turn_right(), down);
+        assert_eq!(down.turn_left(), right);
+        let mut v = Vec2::new(2, 5);
+        for _ in 0..4 {
+            v = v.turn_left();
+        }
+        assert_eq!(v, Vec2::new(2, 5));
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let p = Vec2::new(5, 5);
+        assert!(p.neighbours_4().iter().all(|n| n.manhattan(p) == 1));
+        assert!(p.neighbours_8().iter().all(|n| n.chebyshev(p) == 1));
+        let q = Vec3::new(0, 0, 0);
+        assert!(q.neighbours_6().iter().all(|n| n.manhattan(q) == 1));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let bounds = Bounds::of([Vec2::new(2, 3), Vec2::new(-1, 5), Vec2::new(0, 4)]).unwrap();
+        assert_eq!(bounds.min, Vec2::new(-1, 3));
+        assert_eq!(bounds.max, Vec2::new(2, 5));
+        assert_eq!(bounds.size(), Vec2::new(4, 3));
+        assert!(bounds.contains(Vec2::new(0, 3)));
+        assert!(!bounds.contains(Vec2::new(0, 6)));
+        assert!(bounds.grow(1).contains(Vec2::new(0, 6)));
+        assert_eq!(Bounds::<Vec3>::of([]), None);
+    }
+}
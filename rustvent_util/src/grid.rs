@@ -0,0 +1,324 @@
+use std::fmt::Display;
+use std::ops::{Index, IndexMut};
+
+use crate::error::{Error, Result};
+
+/// A position in a grid: `(x, y)`, with `x` the column and `y` the row.
+pub type Pos = (usize, usize);
+
+const NEIGHBOURS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+const NEIGHBOURS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A rectangular grid of cells, stored row by row.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Fails if the rows differ in length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self> {
+        let width = rows.first().map_or(0, |row| row.len());
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for (y, row) in rows.into_iter().enumerate() {
+            if row.len() != width {
+                return Err(Error::new("Rows differ in length").at_line(y + 1));
+            }
+            cells.extend(row);
+        }
+        Ok(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Parses one cell per character, failing on empty input and rows that differ in length.
+    pub fn parse<S: AsRef<str>>(
+        lines: &[S],
+        parse_cell: impl FnMut(char) -> Result<T>,
+    ) -> Result<Self> {
+        Self::from_rows(parse_rows(lines, parse_cell)?)?.non_empty()
+    }
+
+    /// Like [`Grid::parse`], but fills up rows shorter than the longest one.
+    pub fn parse_padded<S: AsRef<str>>(
+        lines: &[S],
+        fill: T,
+        parse_cell: impl FnMut(char) -> Result<T>,
+    ) -> Result<Self>
+    where
+        T: Clone,
+    {
+        let mut rows = parse_rows(lines, parse_cell)?;
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        for row in &mut rows {
+            row.resize(width, fill.clone());
+        }
+        Self::from_rows(rows)?.non_empty()
+    }
+
+    fn non_empty(self) -> Result<Self> {
+        if self.cells.is_empty() {
+            return Err(Error::new("Empty grid"));
+        }
+        Ok(self)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Converts signed coordinates, returning `None` outside of the grid.
+    pub fn to_pos(&self, x: isize, y: isize) -> Option<Pos> {
+        let pos = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        self.contains(pos).then_some(pos)
+    }
+
+    pub fn contains(&self, (x, y): Pos) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.1 * self.width + pos.0])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.cells[pos.1 * self.width + pos.0])
+        } else {
+            None
+        }
+    }
+
+    /// All positions, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// All cells with their positions, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    fn neighbours<'a>(
+        &'a self,
+        (x, y): Pos,
+        deltas: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = Pos> + 'a {
+        deltas
+            .iter()
+            .filter_map(move |(dx, dy)| self.to_pos(x as isize + dx, y as isize + dy))
+    }
+
+    /// The horizontal and vertical neighbours inside the grid.
+    pub fn neighbours_4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbours(pos, &NEIGHBOURS_4)
+    }
+
+    /// The horizontal, vertical and diagonal neighbours inside the grid.
+    pub fn neighbours_8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbours(pos, &NEIGHBOURS_8)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        assert!(x < self.width, "Column {} out of bounds", x);
+        (0..self.height).map(move |y| &self.cells[y * self.width + x])
+    }
+
+    pub fn columns(
+        &self,
+    ) -> impl Iterator<Item = impl DoubleEndedIterator<Item = &T> + ExactSizeIterator> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// Mirrors the grid along its main diagonal, turning rows into columns.
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width: self.height,
+            height: self.width,
+            cells: self.columns().flatten().cloned().collect(),
+        }
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Adds a row at the bottom; an empty grid takes the row's width.
+    pub fn push_row(&mut self, row: Vec<T>) {
+        if self.cells.is_empty() {
+            self.width = row.len();
+        }
+        assert_eq!(row.len(), self.width, "Row differs in length");
+        self.cells.extend(row);
+        self.height += 1;
+    }
+}
+
+fn parse_rows<S: AsRef<str>, T>(
+    lines: &[S],
+    mut parse_cell: impl FnMut(char) -> Result<T>,
+) -> Result<Vec<Vec<T>>> {
+    crate::parse_lines(lines, |line| {
+        line.chars()
+            .enumerate()
+            .map(|(i, c)| parse_cell(c).map_err(|err| err.at_column(i + 1)))
+            .collect()
+    })
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("Position {:?} out of bounds", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("Position {:?} out of bounds", pos))
+    }
+}
+
+/// Prints every cell without separators, one line per row.
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(lines: &[&str]) -> Grid<u32> {
+        Grid::parse(lines, |c| {
+            c.to_digit(10)
+                .ok_or_else(|| Error::invalid("Invalid digit", c))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = digits(&["123", "456"]);
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 0)], 3);
+        assert_eq!(grid.get((0, 2)), None);
+        assert_eq!(grid.row(1), [4, 5, 6]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), [2, 5]);
+        assert_eq!(grid.to_string(), "123\n456\n");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |lines: &[&str]| Grid::parse(lines, Ok).err();
+        assert_eq!(
+            parse(&["12", "3"]),
+            Some(Error::new("Rows differ in length").at_line(2))
+        );
+        assert_eq!(parse(&[]), Some(Error::new("Empty grid")));
+        let err = Grid::parse(&["12", "3x"], |c| {
+            c.to_digit(10)
+                .ok_or_else(|| Error::invalid("Invalid digit", c))
+        })
+        .err();
+        assert_eq!(
+            err,
+            Some(Error::invalid("Invalid digit", 'x').at_column(2).at_line(2))
+        );
+    }
+
+    #[test]
+    fn test_parse_padded() {
+        let grid = Grid::parse_padded(&[" #", "#", ""], ' ', Ok).unwrap();
+        assert_eq!(grid.to_string(), " #\n# \n  \n");
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = digits(&["123", "456", "789"]);
+        let values = |positions: Vec<Pos>| positions.iter().map(|pos| grid[*pos]).collect();
+        let corner: Vec<u32> = values(grid.neighbours_4((0, 0)).collect());
+        assert_eq!(corner, [2, 4]);
+        let center: Vec<u32> = values(grid.neighbours_4((1, 1)).collect());
+        assert_eq!(center, [2, 4, 6, 8]);
+        let center: Vec<u32> = values(grid.neighbours_8((1, 1)).collect());
+        assert_eq!(center, [1, 2, 3, 4, 6, 7, 8, 9]);
+        let edge: Vec<u32> = values(grid.neighbours_8((2, 1)).collect());
+        assert_eq!(edge, [2, 3, 5, 8, 9]);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let grid = digits(&["123", "456"]);
+        assert_eq!(grid.transpose(), digits(&["14", "25", "36"]));
+        assert_eq!(grid.transpose().transpose(), grid);
+    }
+
+    #[test]
+    fn test_push_row() {
+        let mut grid = Grid::new(0, 0, false);
+        grid.push_row(vec![true, false]);
+        grid.push_row(vec![false, false]);
+        assert_eq!(
+            grid.map(|b| if *b { '#' } else { '.' }).to_string(),
+            "#.\n..\n"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Closed integer intervals and normalized sets of them.
+
+use num::PrimInt;
+
+/// The integers from `first` to `last`, both included. Intervals are never empty, so the
+/// operations that can end up with nothing return an `Option` or an [`IntervalSet`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    pub first: T,
+    pub last: T,
+}
+
+impl<T: PrimInt> Interval<T> {
+    /// The interval, or `None` if `last` comes before `first`.
+    pub fn new(first: T, last: T) -> Option<Self> {
+        (first <= last).then_some(Self { first, last })
+    }
+
+    /// The number of integers in the interval, which must fit into `T`.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> T {
+        self.last - self.first + T::one()
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        self.first <= x && x <= self.last
+    }
+
+    pub fn contains_interval(&self, other: &Self) -> bool {
+        self.first <= other.first && other.last <= self.last
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.first <= other.last && other.first <= self.last
+    }
+
+    /// Whether the intervals overlap or one starts right after the other ends.
+    fn touches(&self, other: &Self) -> bool {
+        self.overlaps(other)
+            || self.last.checked_add(&T::one()) == Some(other.first)
+            || other.last.checked_add(&T::one()) == Some(self.first)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        Self::new(self.first.max(other.first), self.last.min(other.last))
+    }
+
+    pub fn union(&self, other: &Self) -> IntervalSet<T> {
+        [*self, *other].into_iter().collect()
+    }
+
+    /// The integers of `self` that aren't in `other`, at most two intervals.
+    pub fn difference(&self, other: &Self) -> IntervalSet<T> {
+        if !self.overlaps(other) {
+            return IntervalSet::from(*self);
+        }
+        let before = (self.first < other.first).then(|| Self {
+            first: self.first,
+            last: other.first - T::one(),
+        });
+        let after = (other.last < self.last).then(|| Self {
+            first: other.last + T::one(),
+            last: self.last,
+        });
+        before.into_iter().chain(after).collect()
+    }
+}
+
+/// A set of integers, stored as sorted intervals that neither overlap nor touch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self {
+            intervals: Vec::new(),
+        }
+    }
+}
+
+impl<T: PrimInt> From<Interval<T>> for IntervalSet<T> {
+    fn from(interval: Interval<T>) -> Self {
+        Self {
+            intervals: vec![interval],
+        }
+    }
+}
+
+/// Sorts the intervals once and merges those that overlap or touch.
+impl<T: PrimInt> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut sorted: Vec<Interval<T>> = iter.into_iter().collect();
+        sorted.sort_unstable_by_key(|interval| interval.first);
+        let mut intervals: Vec<Interval<T>> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) if last.touches(&interval) => last.last = last.last.max(interval.last),
+                _ => intervals.push(interval),
+            }
+        }
+        Self { intervals }
+    }
+}
+
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The intervals, sorted.
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The number of integers in the set, which must fit into `T`.
+    pub fn len(&self) -> T {
+        self.intervals
+            .iter()
+            .fold(T::zero(), |len, interval| len + interval.len())
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        let after = self.intervals.partition_point(|interval| interval.last < x);
+        self.intervals
+            .get(after)
+            .is_some_and(|interval| interval.contains(x))
+    }
+
+    /// Adds the interval, merging it with those it overlaps or touches.
+    pub fn insert(&mut self, interval: Interval<T>) {
+        let start = self
+            .intervals
+            .partition_point(|other| other.last < interval.first && !other.touches(&interval));
+        let end = self
+            .intervals
+            .partition_point(|other| other.first <= interval.last || other.touches(&interval));
+        let merged = self.intervals[start..end]
+            .iter()
+            .fold(interval, |merged, other| Interval {
+                first: merged.first.min(other.first),
+                last: merged.last.max(other.last),
+            });
+        self.intervals.splice(start..end, [merged]);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.intervals
+            .iter()
+            .chain(&other.intervals)
+            .copied()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.intervals.get(i), other.intervals.get(j)) {
+            intervals.extend(a.intersection(b));
+            if a.last < b.last {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { intervals }
+    }
+
+    /// The integers of `within` that aren't in the set.
+    pub fn complement(&self, within: Interval<T>) -> Self {
+        let mut intervals = Vec::new();
+        let mut next = Some(within.first);
+        for interval in &self.intervals {
+            let Some(first) = next else { break };
+            if interval.last < first {
+                continue;
+            }
+            if interval.first > within.last {
+                break;
+            }
+            if first < interval.first {
+                intervals.push(Interval {
+                    first,
+                    last: interval.first - T::one(),
+                });
+            }
+            next = interval.last.checked_add(&T::one());
+        }
+        if let Some(first) = next {
+            intervals.extend(Interval::new(first, within.last));
+        }
+        Self { intervals }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        match (self.intervals.first(), self.intervals.last()) {
+            (Some(first), Some(last)) => self.intersection(&other.complement(Interval {
+                first: first.first,
+                last: last.last,
+            })),
+            _ => Self::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_interval(rng: &mut Rng) -> Interval<i32> {
+        let first = rng.range(-10..10) as i32;
+        Interval::new(first, rng.range(first as i64..10) as i32).unwrap()
+    }
+
+    fn random_set(rng: &mut Rng) -> IntervalSet<i32> {
+        (0..rng.range(0..4)).map(|_| random_interval(rng)).collect()
+    }
+
+    fn expand<'a>(intervals: impl IntoIterator<Item = &'a Interval<i32>>) -> BTreeSet<i32> {
+        intervals
+            .into_iter()
+            .flat_map(|interval| interval.first..=interval.last)
+            .collect()
+    }
+
+    fn assert_normalized(set: &IntervalSet<i32>) {
+        for pair in set.intervals().windows(2) {
+            assert!(pair[0].last + 1 < pair[1].first, "{:?}", set);
+        }
+    }
+
+    #[test]
+    fn test_interval_against_expansion() {
+        let mut rng = Rng::new(24);
+        for _ in 0..1000 {
+            let a = random_interval(&mut rng);
+            let b = random_interval(&mut rng);
+            let (xs, ys) = (expand([&a]), expand([&b]));
+            assert_eq!(a.len() as usize, xs.len());
+            assert_eq!(
+                a.contains_interval(&b),
+                ys.is_subset(&xs),
+                "{:?} {:?}",
+                a,
+                b
+            );
+            assert_eq!(a.overlaps(&b), !xs.is_disjoint(&ys), "{:?} {:?}", a, b);
+            let intersection = a.intersection(&b);
+            assert_eq!(expand(&intersection), &xs & &ys);
+            assert_eq!(expand(a.union(&b).intervals()), &xs | &ys);
+            assert_eq!(expand(a.difference(&b).intervals()), &xs - &ys);
+            assert_normalized(&a.union(&b));
+            assert_normalized(&a.difference(&b));
+        }
+    }
+
+    #[test]
+    fn test_set_against_expansion() {
+        let mut rng = Rng::new(25);
+        for _ in 0..1000 {
+            let a = random_set(&mut rng);
+            let b = random_set(&mut rng);
+            let (xs, ys) = (expand(a.intervals()), expand(b.intervals()));
+            assert_normalized(&a);
+            assert_eq!(a.len() as usize, xs.len());
+            assert_eq!(a.is_empty(), xs.is_empty());
+            assert!((-12..12).all(|x| a.contains(x) == xs.contains(&x)));
+
+            let mut inserted = a.clone();
+            for interval in b.intervals() {
+                inserted.insert(*interval);
+                assert_normalized(&inserted);
+            }
+            assert_eq!(inserted, a.union(&b));
+            assert_eq!(expand(a.union(&b).intervals()), &xs | &ys);
+            assert_eq!(expand(a.intersection(&b).intervals()), &xs & &ys);
+            assert_eq!(expand(a.difference(&b).intervals()), &xs - &ys);
+
+            let within = random_interval(&mut rng);
+            let complement = a.complement(within);
+            assert_normalized(&complement);
+            assert_eq!(
+                expand(complement.intervals()),
+                &expand([&within]) - &xs,
+                "{:?} within {:?}",
+                a,
+                within
+            );
+        }
+        let full = IntervalSet::from(Interval::new(i32::MIN, i32::MAX).unwrap());
+        assert!(full
+            .complement(Interval::new(i32::MIN, i32::MAX).unwrap())
+            .is_empty());
+    }
+}
@@ -0,0 +1,357 @@
+//! The helpers shared by the Advent of Code solutions: reading and parsing inputs, grids,
+//! geometry, integer intervals, graph search, cycle detection, parallelism, logging and
+//! seeded random numbers.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+
+use crate::error::{Error, Result};
+
+pub mod cycle;
+pub mod error;
+pub mod geom;
+pub mod grid;
+pub mod interval;
+pub mod log;
+pub mod parallel;
+pub mod pattern;
+pub mod rank;
+pub mod rng;
+pub mod search;
+
+pub use grid::Grid;
+
+/// The environment variable naming the data directory.
+pub const DATA_DIR_VAR: &str = "RUSTVENT_DATA_DIR";
+
+static DATA_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+static STDIN: OnceLock<std::result::Result<String, String>> = OnceLock::new();
+
+/// Overrides the data directory given by `RUSTVENT_DATA_DIR`.
+pub fn set_data_dir(dir: impl Into<PathBuf>) {
+    *DATA_DIR.write().unwrap() = Some(dir.into());
+}
+
+/// The directory named inputs are read from, `data` unless configured otherwise.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = DATA_DIR.read().unwrap().as_ref() {
+        return dir.clone();
+    }
+    std::env::var_os(DATA_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("data"))
+}
+
+/// Where an input is read from.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Source {
+    Stdin,
+    Path(PathBuf),
+}
+
+impl Source {
+    /// Resolves an input: `-` is stdin, anything with a path separator or an extension is
+    /// an explicit path and everything else names a `.txt` file in the data directory.
+    pub fn resolve(input: &str) -> Self {
+        if input == "-" {
+            Source::Stdin
+        } else if input.contains(['/', std::path::MAIN_SEPARATOR])
+            || Path::new(input).extension().is_some()
+        {
+            Source::Path(PathBuf::from(input))
+        } else {
+            Source::Path(data_dir().join(format!("{}.txt", input)))
+        }
+    }
+
+    /// Reads the source. Stdin is only read once, so every later read sees the same input.
+    pub fn read(&self) -> Result<String> {
+        match self {
+            Source::Stdin => read_stdin(),
+            Source::Path(path) => read_path(path),
+        }
+    }
+
+    /// Opens the source for reading it bit by bit. Files are buffered as they are read,
+    /// stdin is read as a whole like in [`Source::read`].
+    pub fn open(&self) -> Result<Box<dyn BufRead>> {
+        match self {
+            Source::Stdin => Ok(Box::new(Cursor::new(read_stdin()?))),
+            Source::Path(path) => File::open(path)
+                .map(|file| Box::new(BufReader::new(file)) as Box<dyn BufRead>)
+                .map_err(|err| Error::new(format!("Failed to read {}: {}", path.display(), err))),
+        }
+    }
+}
+
+fn read_stdin() -> Result<String> {
+    STDIN
+        .get_or_init(|| {
+            let mut s = String::new();
+            std::io::stdin()
+                .read_to_string(&mut s)
+                .map(|_| s)
+                .map_err(|err| err.to_string())
+        })
+        .clone()
+        .map_err(|err| Error::new(format!("Failed to read stdin: {}", err)))
+}
+
+pub fn read_path(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    std::fs::read_to_string(path)
+        .map_err(|err| Error::new(format!("Failed to read {}: {}", path.display(), err)))
+}
+
+pub fn read_str(file: &str) -> Result<String> {
+    Source::resolve(file).read()
+}
+
+/// Reads the lines of a file lazily, without their line endings.
+pub fn lines(file: &str) -> Result<LineReader<Box<dyn BufRead>>> {
+    Ok(LineReader::new(Source::resolve(file).open()?))
+}
+
+pub fn read_lines(file: &str) -> Result<Vec<String>> {
+    lines(file)?.collect()
+}
+
+/// Reads lines one at a time, either borrowed from a buffer that's reused for every line
+/// or, as an iterator, owned.
+pub struct LineReader<R> {
+    reader: R,
+    buf: String,
+    /// The number of lines read so far.
+    line: usize,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: String::new(),
+            line: 0,
+        }
+    }
+
+    /// The 1-based number of the line last read.
+    pub fn line_number(&self) -> usize {
+        self.line
+    }
+
+    /// Reads the next line into the reader's buffer, `None` at the end of the input.
+    pub fn read_line(&mut self) -> Result<Option<&str>> {
+        self.buf.clear();
+        let read = self.reader.read_line(&mut self.buf).map_err(|err| {
+            Error::new(format!("Failed to read line: {}", err)).at_line(self.line + 1)
+        })?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        let line = self.buf.strip_suffix('\n').unwrap_or(&self.buf);
+        Ok(Some(line.strip_suffix('\r').unwrap_or(line)))
+    }
+
+    /// Parses every line like [`parse_lines`] does, without keeping the lines around.
+    pub fn parse<T>(mut self, mut parse: impl FnMut(&str) -> Result<T>) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        while let Some(line) = self.read_line()? {
+            let item = parse(line).map_err(|err| err.locate_in(line));
+            items.push(item.map_err(|err| err.at_line(self.line))?);
+        }
+        Ok(items)
+    }
+}
+
+impl<R: BufRead> Iterator for LineReader<R> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_line()
+            .map(|line| line.map(|line| line.to_string()))
+            .transpose()
+    }
+}
+
+/// A block of consecutive non-empty lines.
+#[derive(Debug)]
+pub struct Block {
+    /// The 0-based index of the block's first line in its file.
+    pub start: usize,
+    pub lines: Vec<String>,
+}
+
+impl Deref for Block {
+    type Target = [String];
+    fn deref(&self) -> &Self::Target {
+        &self.lines
+    }
+}
+
+impl Block {
+    /// Parses the block, moving the line numbers of errors to the block's position.
+    pub fn parse<T>(&self, parse: impl FnOnce(&[String]) -> Result<T>) -> Result<T> {
+        parse(&self.lines).map_err(|err| err.shift_lines(self.start))
+    }
+}
+
+/// Groups lines into blocks as they are read.
+pub struct Blocks<I> {
+    lines: I,
+    /// The 0-based index of the next line.
+    next_line: usize,
+}
+
+impl<I> Blocks<I> {
+    pub fn new(lines: I) -> Self {
+        Self {
+            lines,
+            next_line: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<String>>> Iterator for Blocks<I> {
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block = Block {
+            start: self.next_line,
+            lines: Vec::new(),
+        };
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            };
+            self.next_line += 1;
+            if !line.is_empty() {
+                block.lines.push(line);
+            } else if block.lines.is_empty() {
+                block.start = self.next_line;
+            } else {
+                return Some(Ok(block));
+            }
+        }
+        (!block.lines.is_empty()).then_some(Ok(block))
+    }
+}
+
+pub fn lines_to_blocks(lines: Vec<String>) -> Vec<Block> {
+    Blocks::new(lines.into_iter().map(Ok))
+        .map(|block| block.expect("Lines in memory can't fail"))
+        .collect()
+}
+
+pub fn tokenize(line: &str) -> Vec<String> {
+    line.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+/// Reads the blocks of a file lazily.
+pub fn blocks(file: &str) -> Result<Blocks<LineReader<Box<dyn BufRead>>>> {
+    Ok(Blocks::new(lines(file)?))
+}
+
+pub fn read_blocks(file: &str) -> Result<Vec<Block>> {
+    blocks(file)?.collect()
+}
+
+/// Reads blocks of one value per line.
+pub fn read_int_blocks<T: FromStr>(file: &str) -> Result<Vec<Vec<T>>> {
+    blocks(file)?
+        .map(|block| block?.parse(|lines| parse_lines(lines, parse_token)))
+        .collect()
+}
+
+/// Parses a token, reporting the token on failure.
+pub fn parse_token<T: FromStr>(token: &str) -> Result<T> {
+    token
+        .parse()
+        .map_err(|_| Error::invalid("Invalid value", token))
+}
+
+/// Gets a token by index, failing if the line is too short.
+pub fn token<S: AsRef<str>>(tokens: &[S], index: usize) -> Result<&str> {
+    tokens
+        .get(index)
+        .map(|token| token.as_ref())
+        .ok_or_else(|| Error::new(format!("Missing token {}", index + 1)))
+}
+
+/// Gets a block by index, failing if the file has fewer blocks.
+pub fn block(blocks: &[Block], index: usize) -> Result<&Block> {
+    blocks
+        .get(index)
+        .ok_or_else(|| Error::new(format!("Missing block {}", index + 1)))
+}
+
+/// Parses every line, adding line numbers and, where possible, columns to errors.
+pub fn parse_lines<S: AsRef<str>, T>(
+    lines: &[S],
+    mut parse: impl FnMut(&str) -> Result<T>,
+) -> Result<Vec<T>> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.as_ref();
+            parse(line).map_err(|err| err.locate_in(line).at_line(i + 1))
+        })
+        .collect()
+}
+
+pub fn parse_blocks<T>(blocks: &[Block], parse: impl Fn(&[String]) -> Result<T>) -> Result<Vec<T>> {
+    blocks.iter().map(|block| block.parse(&parse)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(Source::resolve("-"), Source::Stdin);
+        assert_eq!(
+            Source::resolve("inputs/01.txt"),
+            Source::Path(PathBuf::from("inputs/01.txt"))
+        );
+        assert_eq!(
+            Source::resolve("01.txt"),
+            Source::Path(PathBuf::from("01.txt"))
+        );
+        assert_eq!(
+            Source::resolve("01-test"),
+            Source::Path(data_dir().join("01-test.txt"))
+        );
+    }
+
+    #[test]
+    fn test_line_reader() {
+        let mut reader = LineReader::new(Cursor::new("a b\r\n\nc\n"));
+        assert_eq!(reader.read_line().unwrap(), Some("a b"));
+        assert_eq!(reader.read_line().unwrap(), Some(""));
+        assert_eq!(reader.line_number(), 2);
+        let rest: Vec<String> = reader.collect::<Result<_>>().unwrap();
+        assert_eq!(rest, ["c"]);
+
+        let reader = LineReader::new(Cursor::new("1\n2\nx3\n"));
+        let err = reader.parse(parse_token::<i32>).unwrap_err();
+        assert_eq!(err.to_string(), "3:1: Invalid value: \"x3\"");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let lines = LineReader::new(Cursor::new("\n1\n2\n\n\n3\n\n"));
+        let blocks: Vec<Block> = Blocks::new(lines).collect::<Result<_>>().unwrap();
+        let starts: Vec<usize> = blocks.iter().map(|block| block.start).collect();
+        assert_eq!(starts, [1, 5]);
+        assert_eq!(blocks[0].lines, ["1", "2"]);
+        assert_eq!(blocks[1].lines, ["3"]);
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt::Arguments;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::RwLock;
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    /// Parses a level name, with `off` giving `None`.
+    fn parse(s: &str) -> Result<Option<Self>> {
+        Ok(Some(match s.to_ascii_lowercase().as_str() {
+            "off" => return Ok(None),
+            "error" => Level::Error,
+            "warn" => Level::Warn,
+            "info" => Level::Info,
+            "debug" => Level::Debug,
+            "trace" => Level::Trace,
+            _ => return Err(Error::invalid("Unknown log level", s)),
+        }))
+    }
+}
+
+/// Sets the level of a target, or of everything without a directive of its own.
+#[derive(Debug, PartialEq, Eq)]
+struct Directive {
+    target: Option<String>,
+    level: Option<Level>,
+}
+
+static DIRECTIVES: RwLock<Vec<Directive>> = RwLock::new(Vec::new());
+
+/// The most verbose level of any directive, so that disabled messages skip the lock.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+fn parse_directives(spec: &str) -> Result<Vec<Directive>> {
+    spec.split(',')
+        .filter(|directive| !directive.trim().is_empty())
+        .map(|directive| {
+            let directive = directive.trim();
+            Ok(match directive.split_once('=') {
+                Some((target, level)) => Directive {
+                    target: Some(target.trim().to_string()),
+                    level: Level::parse(level.trim())?,
+                },
+                None => Directive {
+                    target: None,
+                    level: Level::parse(directive)?,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Enables logging as given by a comma-separated list of `target=level` directives and
+/// optionally a bare level for all other targets, like `day22=trace,info`. Targets are
+/// module paths without the crate name, like `day_22` or `search` for the helpers, and may
+/// leave out the underscores. Logging is off by default.
+pub fn configure(spec: &str) -> Result<()> {
+    let directives = parse_directives(spec)?;
+    let max_level = directives
+        .iter()
+        .filter_map(|directive| directive.level)
+        .max()
+        .map_or(0, |level| level as u8);
+    *DIRECTIVES.write().unwrap() = directives;
+    MAX_LEVEL.store(max_level, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The module path without the crate name, e.g. `day_22` for `rustvent_2022::day_22` and
+/// `search` for `rustvent_util::search`.
+fn target(module_path: &str) -> &str {
+    module_path
+        .split_once("::")
+        .map_or(module_path, |(_, target)| target)
+}
+
+/// Whether a directive's target is the module or one of its parents.
+fn matches(directive_target: &str, target: &str) -> bool {
+    let without_underscores = |s: &str| s.replace('_', "");
+    let (directive_target, target) = (
+        without_underscores(directive_target),
+        without_underscores(target),
+    );
+    target == directive_target
+        || target
+            .strip_prefix(&directive_target)
+            .is_some_and(|rest| rest.starts_with("::"))
+}
+
+fn level_of(directives: &[Directive], target: &str) -> Option<Level> {
+    directives
+        .iter()
+        .rev()
+        .find(|directive| {
+            directive
+                .target
+                .as_ref()
+                .is_some_and(|directive_target| matches(directive_target, target))
+        })
+        .or_else(|| {
+            directives
+                .iter()
+                .rev()
+                .find(|directive| directive.target.is_none())
+        })
+        .and_then(|directive| directive.level)
+}
+
+/// Whether messages of `level` from the module at `module_path` are logged.
+pub fn enabled(module_path: &str, level: Level) -> bool {
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+    level_of(&DIRECTIVES.read().unwrap(), target(module_path)).is_some_and(|max| level <= max)
+}
+
+/// Writes a message to stderr, use the macros instead.
+pub fn write(module_path: &str, level: Level, args: Arguments) {
+    eprintln!("{} {}: {}", level.as_str(), target(module_path), args);
+}
+
+/// Logs a message at a level if it's enabled for the calling module. The arguments are only
+/// evaluated then.
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::log::enabled(module_path!(), $level) {
+            $crate::log::write(module_path!(), $level, format_args!($($arg)+));
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::log!($crate::log::Level::Debug, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        $crate::log!($crate::log::Level::Trace, $($arg)+)
+    };
+}
+
+pub use crate::{debug, trace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directives() {
+        assert_eq!(
+            parse_directives("day22=trace, info").unwrap(),
+            [
+                Directive {
+                    target: Some("day22".to_string()),
+                    level: Some(Level::Trace),
+                },
+                Directive {
+                    target: None,
+                    level: Some(Level::Info),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_directives("day22=loud").err(),
+            Some(Error::invalid("Unknown log level", "loud"))
+        );
+    }
+
+    #[test]
+    fn test_level_of() {
+        let directives = parse_directives("debug,day22=trace,search=off").unwrap();
+        assert_eq!(level_of(&directives, "day_22"), Some(Level::Trace));
+        assert_eq!(level_of(&directives, "day_2"), Some(Level::Debug));
+        assert_eq!(level_of(&directives, "search"), None);
+        assert_eq!(level_of(&directives, "searches"), Some(Level::Debug));
+        assert_eq!(level_of(&[], "day_22"), None);
+        assert_eq!(target("rustvent_2022::day_22"), "day_22");
+        assert_eq!(target("rustvent_util::search"), "search");
+    }
+
+    #[test]
+    fn test_search_target() {
+        configure("search=debug").unwrap();
+        let search = enabled("rustvent_util::search", Level::Debug);
+        let search_trace = enabled("rustvent_util::search", Level::Trace);
+        let grid = enabled("rustvent_util::grid", Level::Debug);
+        configure("").unwrap();
+        assert!(search);
+        assert!(!search_trace);
+        assert!(!grid);
+    }
+}
@@ -0,0 +1,109 @@
+use std::cell::Cell;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+static JOBS: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    /// Whether this thread is one of the workers, which run everything they're given
+    /// sequentially instead of starting threads of their own.
+    static IN_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets how many threads independent work may be spread over, 1 by default.
+pub fn set_jobs(jobs: usize) {
+    assert!(jobs > 0, "Need at least one job");
+    JOBS.store(jobs, Ordering::Relaxed);
+}
+
+/// How many threads work started on this thread is spread over.
+pub fn jobs() -> usize {
+    if IN_WORKER.get() {
+        1
+    } else {
+        JOBS.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs `work` on `jobs()` new threads at once and waits for them. Only those threads are
+/// marked as workers, the current one can go on spreading work after this.
+fn spawn(work: impl Fn() + Sync) {
+    let jobs = jobs();
+    if jobs == 1 {
+        return work();
+    }
+    let worker = || {
+        IN_WORKER.set(true);
+        work()
+    };
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..jobs).map(|_| scope.spawn(worker)).collect();
+        for handle in handles {
+            if let Err(err) = handle.join() {
+                std::panic::resume_unwind(err);
+            }
+        }
+    })
+}
+
+/// Splits `0..len` into one range of similar length per job and maps each range on its own
+/// thread, returning the results in order. With a single job, `f` gets the whole range.
+pub fn map_ranges<R: Send>(len: usize, f: impl Fn(Range<usize>) -> R + Sync) -> Vec<R> {
+    let count = jobs().min(len).max(1);
+    let ranges: Vec<Range<usize>> = (0..count)
+        .map(|i| i * len / count..(i + 1) * len / count)
+        .collect();
+    map(&ranges, |range| f(range.clone()))
+}
+
+/// Like [`map_ranges`], but on chunks of `items`.
+pub fn map_chunks<T: Sync, R: Send>(items: &[T], f: impl Fn(&[T]) -> R + Sync) -> Vec<R> {
+    map_ranges(items.len(), |range| f(&items[range]))
+}
+
+/// Maps every item, handing out the next item to whichever thread is done first. The
+/// results are in the order of the items.
+pub fn map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+    spawn(|| loop {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        let Some(item) = items.get(i) else {
+            break;
+        };
+        let res = f(item);
+        results.lock().unwrap().push((i, res));
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_unstable_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, res)| res).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let items: Vec<u64> = (0..100).collect();
+        let expected: Vec<u64> = items.iter().map(|x| x * x).collect();
+        assert_eq!(map(&items, |x| x * x), expected);
+        set_jobs(4);
+        assert_eq!(map(&items, |x| x * x), expected);
+        let sums = map_chunks(&items, |chunk| chunk.iter().sum::<u64>());
+        assert_eq!(sums.len(), 4);
+        assert_eq!(sums.iter().sum::<u64>(), 4950);
+        let ranges = map_ranges(10, |range| range);
+        assert_eq!(ranges, [0..2, 2..5, 5..7, 7..10]);
+        assert_eq!(map_ranges(2, |range| range), [0..1, 1..2]);
+        assert_eq!(map_ranges(0, |range| range.len()), [0]);
+        // Workers don't spread their work any further.
+        let nested = map(&[0, 1], |_| map_ranges(10, |range| range).len());
+        assert_eq!(nested, [1, 1]);
+        assert_eq!(jobs(), 4);
+        assert_eq!(map_ranges(10, |range| range).len(), 4);
+        set_jobs(1);
+    }
+}
@@ -0,0 +1,76 @@
+//! Pulling the values out of a line without depending on its exact wording.
+
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::parse_token;
+
+/// The integers in a line, in order. A `-` right before the digits is a sign unless it
+/// follows a digit, so `x=-2` is `-2` but `2-4` is `2` and `4`.
+pub fn ints<T: FromStr>(line: &str) -> Result<Vec<T>> {
+    let bytes = line.as_bytes();
+    let mut ints = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let mut start = i;
+        if start > 0
+            && bytes[start - 1] == b'-'
+            && (start < 2 || !bytes[start - 2].is_ascii_digit())
+        {
+            start -= 1;
+        }
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        ints.push(parse_token(&line[start..i])?);
+    }
+    Ok(ints)
+}
+
+/// Exactly `N` integers of a line, as found by [`ints`].
+pub fn int_array<T: FromStr, const N: usize>(line: &str) -> Result<[T; N]> {
+    let ints = ints(line)?;
+    let count = ints.len();
+    ints.try_into()
+        .map_err(|_| Error::new(format!("Expected {} numbers, found {}", N, count)))
+}
+
+/// The alphanumeric words of a line, skipping punctuation and whitespace.
+pub fn words(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ints() {
+        let line = "Sensor at x=2, y=-18: closest beacon is at x=-2, y=15";
+        assert_eq!(ints::<i32>(line).unwrap(), [2, -18, -2, 15]);
+        assert_eq!(ints::<u8>("2-4,6-8").unwrap(), [2, 4, 6, 8]);
+        assert_eq!(
+            ints::<u8>("- -3").unwrap_err().to_string(),
+            "Invalid value: \"-3\""
+        );
+        assert_eq!(int_array::<u32, 2>("Valve 7 at 3").unwrap(), [7, 3]);
+        assert_eq!(
+            int_array::<u32, 3>("Valve 7 at 3").unwrap_err().to_string(),
+            "Expected 3 numbers, found 2"
+        );
+    }
+
+    #[test]
+    fn test_words() {
+        let words: Vec<&str> = words("Valve AA has flow rate=0; tunnels lead to DD, II").collect();
+        assert_eq!(
+            words,
+            ["Valve", "AA", "has", "flow", "rate", "0", "tunnels", "lead", "to", "DD", "II"]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Picking the largest items without sorting all of them.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// The `n` items with the largest keys seen so far, kept in a heap of at most `n` items.
+/// Of items with equal keys, the ones pushed first rank higher.
+pub struct TopN<K, T> {
+    n: usize,
+    pushed: usize,
+    heap: BinaryHeap<Reverse<Entry<K, T>>>,
+}
+
+struct Entry<K, T> {
+    key: K,
+    index: usize,
+    item: T,
+}
+
+impl<K: Ord, T> Ord for Entry<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key).then(other.index.cmp(&self.index))
+    }
+}
+
+impl<K: Ord, T> PartialOrd for Entry<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> PartialEq for Entry<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord, T> Eq for Entry<K, T> {}
+
+impl<K: Ord, T> TopN<K, T> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            pushed: 0,
+            heap: BinaryHeap::with_capacity(n + 1),
+        }
+    }
+
+    pub fn push(&mut self, key: K, item: T) {
+        let entry = Entry {
+            key,
+            index: self.pushed,
+            item,
+        };
+        self.pushed += 1;
+        if self.heap.len() < self.n {
+            self.heap.push(Reverse(entry));
+        } else if self
+            .heap
+            .peek()
+            .is_some_and(|Reverse(lowest)| entry > *lowest)
+        {
+            self.heap.pop();
+            self.heap.push(Reverse(entry));
+        }
+    }
+
+    /// The items, the one with the largest key first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(entry)| entry.item)
+            .collect()
+    }
+}
+
+/// The `n` items with the largest keys, largest first, with their indices in `items`.
+pub fn top_n_by_key<T, K: Ord>(
+    items: impl IntoIterator<Item = T>,
+    n: usize,
+    mut key: impl FnMut(&T) -> K,
+) -> Vec<(usize, T)> {
+    let mut top = TopN::new(n);
+    for (index, item) in items.into_iter().enumerate() {
+        top.push(key(&item), (index, item));
+    }
+    top.into_sorted_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_n_by_key() {
+        let words = ["b", "ccc", "a", "dd", "eee", "f"];
+        assert_eq!(
+            top_n_by_key(words, 3, |word| word.len()),
+            [(1, "ccc"), (4, "eee"), (3, "dd")]
+        );
+        assert_eq!(top_n_by_key(words, 0, |word| word.len()), []);
+        assert_eq!(top_n_by_key(words, 10, |word| word.len()).len(), 6);
+
+        let mut rng = crate::rng::Rng::new(3);
+        let numbers: Vec<i64> = (0..200).map(|_| rng.range(0..50)).collect();
+        let mut sorted: Vec<(usize, i64)> = numbers.iter().copied().enumerate().collect();
+        sorted.sort_by_key(|(index, x)| (Reverse(*x), *index));
+        assert_eq!(top_n_by_key(numbers, 20, |x| *x), sorted[..20]);
+    }
+}
@@ -0,0 +1,75 @@
+//! A small seeded random number generator for generating inputs. The same seed always gives
+//! the same numbers, on every platform.
+
+use std::ops::Range;
+
+/// SplitMix64, good enough for test data and not meant for anything else.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `range`, which must not be empty.
+    pub fn range(&mut self, range: Range<i64>) -> i64 {
+        assert!(!range.is_empty(), "Empty range {:?}", range);
+        let len = range.end.abs_diff(range.start);
+        // The bias of the modulo is negligible for the small ranges inputs use.
+        range.start.wrapping_add((self.next_u64() % len) as i64)
+    }
+
+    /// An index into something of length `len`.
+    pub fn index(&mut self, len: usize) -> usize {
+        self.range(0..len as i64) as usize
+    }
+
+    /// `true` with the given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.index(i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let numbers: Vec<i64> = {
+            let mut rng = Rng::new(7);
+            (0..1000).map(|_| rng.range(-3..4)).collect()
+        };
+        assert!(numbers.iter().all(|x| (-3..4).contains(x)));
+        assert!((-3..4).all(|x| numbers.contains(&x)));
+        let mut rng = Rng::new(7);
+        assert!(numbers.iter().all(|x| *x == rng.range(-3..4)));
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+
+        let mut items: Vec<usize> = (0..10).collect();
+        rng.shuffle(&mut items);
+        items.sort();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+}
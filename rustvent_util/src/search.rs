@@ -0,0 +1,257 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use num::Zero;
+
+use crate::log::debug;
+
+/// A shortest path: its total cost and every node from the start to the goal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path<N, C = usize> {
+    pub cost: C,
+    pub nodes: Vec<N>,
+}
+
+impl<N, C> Path<N, C> {
+    pub fn start(&self) -> &N {
+        &self.nodes[0]
+    }
+
+    pub fn goal(&self) -> &N {
+        &self.nodes[self.nodes.len() - 1]
+    }
+}
+
+/// Every node seen so far with its best known cost and the node it was reached from.
+struct Tree<N, C> {
+    nodes: Vec<(N, C, Option<usize>)>,
+    index: HashMap<N, usize>,
+}
+
+impl<N: Clone + Eq + Hash, C: Copy + Ord> Tree<N, C> {
+    fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Records a way to reach `node`, returning its index if it's the best one so far.
+    fn reach(&mut self, node: N, cost: C, parent: Option<usize>) -> Option<usize> {
+        match self.index.entry(node) {
+            Entry::Occupied(entry) => {
+                let i = *entry.get();
+                if self.nodes[i].1 <= cost {
+                    return None;
+                }
+                self.nodes[i].1 = cost;
+                self.nodes[i].2 = parent;
+                Some(i)
+            }
+            Entry::Vacant(entry) => {
+                let i = self.nodes.len();
+                self.nodes.push((entry.key().clone(), cost, parent));
+                entry.insert(i);
+                Some(i)
+            }
+        }
+    }
+
+    fn path(&self, goal: usize) -> Path<N, C> {
+        let mut nodes = Vec::new();
+        let mut current = Some(goal);
+        while let Some(i) = current {
+            nodes.push(self.nodes[i].0.clone());
+            current = self.nodes[i].2;
+        }
+        nodes.reverse();
+        Path {
+            cost: self.nodes[goal].1,
+            nodes,
+        }
+    }
+}
+
+/// Finds the nearest goal from any of the starts, with every step costing one.
+pub fn bfs<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Option<Path<N>>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut tree = Tree::new();
+    let mut queue: VecDeque<usize> = starts
+        .into_iter()
+        .filter_map(|start| tree.reach(start, 0, None))
+        .collect();
+    while let Some(i) = queue.pop_front() {
+        let (node, cost, _) = &tree.nodes[i];
+        if is_goal(node) {
+            debug!("Found a goal after exploring {} nodes", tree.nodes.len());
+            return Some(tree.path(i));
+        }
+        let cost = cost + 1;
+        for next in neighbours(&node.clone()) {
+            queue.extend(tree.reach(next, cost, Some(i)));
+        }
+    }
+    debug!("No goal among {} nodes", tree.nodes.len());
+    None
+}
+
+/// The number of steps from the nearest start to every reachable node.
+pub fn distances<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbours: impl FnMut(&N) -> I,
+) -> HashMap<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if let Entry::Vacant(entry) = distances.entry(start.clone()) {
+            entry.insert(0);
+            queue.push_back((start, 0));
+        }
+    }
+    while let Some((node, distance)) = queue.pop_front() {
+        for next in neighbours(&node) {
+            if let Entry::Vacant(entry) = distances.entry(next.clone()) {
+                entry.insert(distance + 1);
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    distances
+}
+
+/// Finds the cheapest goal from any of the starts. `neighbours` yields every next node with
+/// the cost of the step there, which must not be negative.
+pub fn dijkstra<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbours: impl FnMut(&N) -> I,
+    is_goal: impl FnMut(&N) -> bool,
+) -> Option<Path<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(starts, neighbours, |_| C::zero(), is_goal)
+}
+
+/// Like [`dijkstra`], but explores the nodes with the lowest cost plus `heuristic` first. The
+/// path is only guaranteed to be the cheapest if the heuristic never overestimates the cost
+/// to the nearest goal.
+pub fn astar<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Option<Path<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut tree = Tree::new();
+    let mut heap = BinaryHeap::new();
+    for start in starts {
+        let estimate = heuristic(&start);
+        if let Some(i) = tree.reach(start, C::zero(), None) {
+            heap.push(Reverse((estimate, C::zero(), i)));
+        }
+    }
+    while let Some(Reverse((_, cost, i))) = heap.pop() {
+        let node = &tree.nodes[i].0;
+        if cost > tree.nodes[i].1 {
+            // Reached more cheaply since this entry was pushed.
+            continue;
+        }
+        if is_goal(node) {
+            debug!("Found a goal after exploring {} nodes", tree.nodes.len());
+            return Some(tree.path(i));
+        }
+        for (next, step) in neighbours(&node.clone()) {
+            let next_cost = cost + step;
+            let estimate = next_cost + heuristic(&next);
+            if let Some(j) = tree.reach(next, next_cost, Some(i)) {
+                heap.push(Reverse((estimate, next_cost, j)));
+            }
+        }
+    }
+    debug!("No goal among {} nodes", tree.nodes.len());
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A weighted graph where the direct edge from 0 to 3 is more expensive than the detour.
+    fn weighted(node: &u32) -> Vec<(u32, u32)> {
+        match node {
+            0 => vec![(1, 1), (3, 10)],
+            1 => vec![(2, 2)],
+            2 => vec![(3, 3)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_bfs() {
+        let neighbours = |n: &i32| [n - 1, n + 1].into_iter().filter(|n| (0..10).contains(n));
+        let path = bfs([2], neighbours, |n| *n == 5).unwrap();
+        assert_eq!(path.cost, 3);
+        assert_eq!(path.nodes, [2, 3, 4, 5]);
+        let path = bfs([0, 9], neighbours, |n| *n == 6).unwrap();
+        assert_eq!(path.nodes, [9, 8, 7, 6]);
+        assert_eq!(bfs([0], neighbours, |n| *n == 10), None);
+        assert_eq!(bfs([4], neighbours, |n| *n == 4).unwrap().nodes, [4]);
+    }
+
+    #[test]
+    fn test_distances() {
+        let neighbours = |n: &u32| [n * 2, n + 1].into_iter().filter(|n| *n <= 10);
+        let distances = distances([1], neighbours);
+        assert_eq!(distances.len(), 10);
+        assert_eq!(distances[&1], 0);
+        assert_eq!(distances[&8], 3);
+        assert_eq!(distances[&10], 4);
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let path = dijkstra([0], weighted, |n| *n == 3).unwrap();
+        assert_eq!(path.cost, 6);
+        assert_eq!(path.nodes, [0, 1, 2, 3]);
+        assert_eq!((*path.start(), *path.goal()), (0, 3));
+        assert_eq!(dijkstra([1], weighted, |n| *n == 0), None);
+    }
+
+    #[test]
+    fn test_astar() {
+        let goal = (4, 3);
+        let neighbours = |&(x, y): &(i32, i32)| {
+            [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .into_iter()
+                .filter(|&(x, y)| (0..6).contains(&x) && (0..6).contains(&y) && (x, y) != (2, 1))
+                .map(|next| (next, 1))
+        };
+        let manhattan = |&(x, y): &(i32, i32)| (goal.0 - x).abs() + (goal.1 - y).abs();
+        let path = astar([(0, 1)], neighbours, manhattan, |n| *n == goal).unwrap();
+        let expected = dijkstra([(0, 1)], neighbours, |n| *n == goal).unwrap();
+        assert_eq!(path.cost, 6);
+        assert_eq!(path.cost, expected.cost);
+        assert_eq!(path.nodes.len(), 7);
+        let exact = |n: &u32| [6, 5, 3, 0][*n as usize];
+        assert_eq!(astar([0], weighted, exact, |n| *n == 3).unwrap().cost, 6);
+    }
+}
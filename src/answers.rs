@@ -0,0 +1,179 @@
+use crate::days;
+use crate::error::{Error, Result};
+use crate::solution::Answer;
+use crate::util;
+
+mod toml;
+
+use toml::{Pair, Value};
+
+/// The expected answers for one data file.
+pub struct Entry {
+    pub input: String,
+    pub day: u8,
+    pub answers: Vec<(u8, Answer)>,
+}
+
+/// The answer of a part next to the expected one.
+pub struct Outcome {
+    pub part: u8,
+    pub expected: Answer,
+    pub actual: Answer,
+}
+
+impl Outcome {
+    pub fn is_ok(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+impl Entry {
+    fn new(input: &str) -> Result<Self> {
+        let day = input
+            .get(..2)
+            .and_then(|prefix| prefix.parse().ok())
+            .filter(|day| days::find(*day).is_some())
+            .ok_or_else(|| Error::invalid("Expected an input name starting with a day", input))?;
+        Ok(Self {
+            input: input.to_string(),
+            day,
+            answers: Vec::new(),
+        })
+    }
+
+    pub fn answer(&self, part: u8) -> Option<&Answer> {
+        self.answers
+            .iter()
+            .find(|(answer_part, _)| *answer_part == part)
+            .map(|(_, answer)| answer)
+    }
+
+    /// Solves the part, which must have an expected answer.
+    pub fn verify_part(&self, part: u8) -> Result<Outcome> {
+        let expected = self
+            .answer(part)
+            .ok_or_else(|| Error::new(format!("No answer for part {}", part)))?;
+        let day = days::find(self.day).expect("Day not registered");
+        let parsed = day.parse_named(&self.input)?;
+        Ok(Outcome {
+            part,
+            expected: expected.clone(),
+            actual: parsed.part(part),
+        })
+    }
+
+    /// Solves every part with an expected answer.
+    pub fn verify(&self) -> Result<Vec<Outcome>> {
+        let day = days::find(self.day).expect("Day not registered");
+        let parsed = day.parse_named(&self.input)?;
+        Ok(self
+            .answers
+            .iter()
+            .map(|(part, expected)| Outcome {
+                part: *part,
+                expected: expected.clone(),
+                actual: parsed.part(*part),
+            })
+            .collect())
+    }
+}
+
+/// Loads `answers.toml` from the data directory.
+pub fn load() -> Result<Vec<Entry>> {
+    let path = util::data_dir().join("answers.toml");
+    parse(&util::read_path(&path)?).map_err(|err| err.in_file(&path.display().to_string()))
+}
+
+/// Reads the entries from the tables of the answers file. Only the build script needs the
+/// slow parts, see [`toml::slow_parts`], so they are just checked here.
+fn parse(text: &str) -> Result<Vec<Entry>> {
+    let located = |line: usize, err: Error| {
+        let content = text.lines().nth(line - 1).unwrap_or_default().trim();
+        err.locate_in(content).at_line(line)
+    };
+    let tables = toml::parse(text).map_err(|err| {
+        let error = match err.token {
+            Some(token) => Error::invalid(err.message, token),
+            None => Error::new(err.message),
+        };
+        located(err.line, error)
+    })?;
+    let mut entries = Vec::new();
+    for table in tables {
+        let mut entry = Entry::new(&table.name).map_err(|err| located(table.line, err))?;
+        for Pair { key, value, line } in table.pairs {
+            let invalid = |message: &str| {
+                let content = text.lines().nth(line - 1).unwrap_or_default().trim();
+                located(line, Error::invalid(message, content))
+            };
+            match (key.as_str(), value) {
+                ("slow", value) => {
+                    toml::slow_parts(&value).map_err(invalid)?;
+                }
+                ("part_1" | "part_2", value) => {
+                    let part = if key == "part_1" { 1 } else { 2 };
+                    let answer = match value {
+                        Value::Int(x) => x.into(),
+                        Value::Str(s) => s.into(),
+                        Value::Bool(_) | Value::Ints(_) => {
+                            return Err(invalid("Expected an answer"))
+                        }
+                    };
+                    entry.answers.push((part, answer));
+                }
+                _ => return Err(located(line, Error::invalid("Unknown key", key))),
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generated by the build script for every answer in the answers file.
+    fn assert_answer(input: &str, part: u8) {
+        let entries = load().unwrap();
+        let entry = entries
+            .iter()
+            .find(|entry| entry.input == input)
+            .expect("Input not in the answers file");
+        let outcome = entry.verify_part(part).unwrap();
+        assert_eq!(outcome.actual, outcome.expected);
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/answer_tests.rs"));
+
+    #[test]
+    fn test_parse() {
+        let entries = parse(
+            "# comment\n[10-test]\nslow = [2]\npart_1 = 42\npart_2 = \"\"\"\n\n#.\n.#\"\"\"\n",
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].day, 10);
+        assert_eq!(entries[0].answer(1), Some(&Answer::Int(42)));
+        assert_eq!(entries[0].answer(2), Some(&"\n#.\n.#".into()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse("[01-test]\npart_3 = 1\n").err().unwrap();
+        assert_eq!(
+            err,
+            Error::invalid("Unknown key", "part_3")
+                .at_column(1)
+                .at_line(2)
+        );
+        let err = parse("[1-test]\n").err().unwrap();
+        assert_eq!(err.line, Some(1));
+        let err = parse("[01-test]\npart_1 = 1\npart_1 = 2\n").err().unwrap();
+        assert_eq!(err.line, Some(3));
+        let err = parse("[01-test]\nslow = [3]\n").err().unwrap();
+        assert_eq!(err.line, Some(2));
+        assert_eq!(toml::slow_parts(&Value::Ints(vec![2])), Ok(vec![2]));
+        assert_eq!(toml::slow_parts(&Value::Bool(true)), Ok(vec![1, 2]));
+    }
+}
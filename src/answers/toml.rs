@@ -0,0 +1,140 @@
+//! The small subset of TOML the answers file needs. It depends on nothing else in the crate
+//! so that the build script can use it too.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Str(String),
+    Bool(bool),
+    Ints(Vec<i64>),
+}
+
+/// A key and its value, with the 1-based line it is on.
+pub struct Pair {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+pub struct Table {
+    pub name: String,
+    pub line: usize,
+    pub pairs: Vec<Pair>,
+}
+
+/// What went wrong where, for the caller to turn into its own error type.
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub message: &'static str,
+    pub token: Option<String>,
+    pub line: usize,
+}
+
+fn parse_value(s: &str) -> Result<Value, (&'static str, &str)> {
+    match s {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => (),
+    }
+    if let Some(inner) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        if inner.contains(['"', '\\']) {
+            return Err(("Escapes aren't supported", s));
+        }
+        return Ok(Value::Str(inner.to_string()));
+    }
+    if let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        return inner
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| item.parse().map_err(|_| ("Invalid value", item)))
+            .collect::<Result<_, _>>()
+            .map(Value::Ints);
+    }
+    s.parse().map(Value::Int).map_err(|_| ("Invalid value", s))
+}
+
+/// The parts a `slow` value marks as too slow for unoptimized builds: all of them for `true`
+/// or a list of them, like `[2]`.
+pub fn slow_parts(value: &Value) -> Result<Vec<u8>, &'static str> {
+    match value {
+        Value::Bool(slow) => Ok(if *slow { vec![1, 2] } else { Vec::new() }),
+        Value::Ints(parts) => parts
+            .iter()
+            .map(|part| match part {
+                1 | 2 => Ok(*part as u8),
+                _ => Err("Expected parts 1 or 2"),
+            })
+            .collect(),
+        _ => Err("Expected a boolean or a list of parts"),
+    }
+}
+
+/// Parses tables of integer, string, boolean and integer array values. Multi-line strings
+/// start with `"""` at the end of a line and keep every line up to the closing `"""`.
+pub fn parse(text: &str) -> Result<Vec<Table>, SyntaxError> {
+    let mut tables: Vec<Table> = Vec::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((i, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message, token: &str| SyntaxError {
+            message,
+            token: Some(token.to_string()),
+            line: i + 1,
+        };
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .ok_or_else(|| error("Invalid table header", line))?;
+            if tables.iter().any(|table| table.name == name) {
+                return Err(error("Duplicate table", name));
+            }
+            tables.push(Table {
+                name: name.to_string(),
+                line: i + 1,
+                pairs: Vec::new(),
+            });
+            continue;
+        }
+        let table = tables.last_mut().ok_or(SyntaxError {
+            message: "Expected a table header",
+            token: None,
+            line: i + 1,
+        })?;
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("Expected a key and a value", line))?;
+        let (key, value) = (key.trim(), value.trim());
+        if table.pairs.iter().any(|pair| pair.key == key) {
+            return Err(error("Duplicate key", key));
+        }
+        let value = if value == "\"\"\"" {
+            let mut s = String::new();
+            loop {
+                let (_, line) = lines.next().ok_or(SyntaxError {
+                    message: "Unterminated string",
+                    token: None,
+                    line: i + 1,
+                })?;
+                if let Some(last) = line.strip_suffix("\"\"\"") {
+                    s.push_str(last);
+                    break;
+                }
+                s.push_str(line);
+                s.push('\n');
+            }
+            Value::Str(s)
+        } else {
+            parse_value(value).map_err(|(message, token)| error(message, token))?
+        };
+        table.pairs.push(Pair {
+            key: key.to_string(),
+            value,
+            line: i + 1,
+        });
+    }
+    Ok(tables)
+}
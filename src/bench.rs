@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+use crate::days::Day;
+use crate::error::Result;
+use crate::solution::InputKind;
+
+/// The durations of one step over all runs, sorted.
+pub struct Timings {
+    pub step: &'static str,
+    pub durations: Vec<Duration>,
+}
+
+impl Timings {
+    fn new(step: &'static str, mut durations: Vec<Duration>) -> Self {
+        durations.sort();
+        Self { step, durations }
+    }
+
+    pub fn min(&self) -> Duration {
+        self.durations[0]
+    }
+
+    pub fn median(&self) -> Duration {
+        self.durations[self.durations.len() / 2]
+    }
+
+    pub fn max(&self) -> Duration {
+        self.durations[self.durations.len() - 1]
+    }
+}
+
+pub(crate) fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let res = f();
+    (res, start.elapsed())
+}
+
+/// Times parsing and both parts of a day separately, each `runs` times.
+pub fn bench(day: &Day, input: &str, kind: InputKind, runs: usize) -> Result<Vec<Timings>> {
+    assert!(runs > 0, "Need at least one run");
+    let mut parse_durations = Vec::new();
+    let mut part_durations = [Vec::new(), Vec::new()];
+    for _ in 0..runs {
+        let (parsed, duration) = time(|| (day.parse)(input, kind));
+        let parsed = parsed?;
+        parse_durations.push(duration);
+        for (part, durations) in (1..).zip(&mut part_durations) {
+            durations.push(time(|| parsed.part(part)).1);
+        }
+    }
+    let [part_1, part_2] = part_durations;
+    Ok(vec![
+        Timings::new("parse", parse_durations),
+        Timings::new("part 1", part_1),
+        Timings::new("part 2", part_2),
+    ])
+}
+
+pub fn print_header() {
+    println!(
+        "{:<3}  {:<12}  {:<6}  {:>12}  {:>12}  {:>12}",
+        "Day", "Input", "Step", "Min (ms)", "Median (ms)", "Max (ms)"
+    );
+}
+
+pub fn print_row(day: &Day, input: &str, timings: &Timings) {
+    let ms = |duration: Duration| format!("{:.3}", duration.as_secs_f64() * 1000.0);
+    println!(
+        "{:<3}  {:<12}  {:<6}  {:>12}  {:>12}  {:>12}",
+        format!("{:02}", day.number),
+        input,
+        timings.step,
+        ms(timings.min()),
+        ms(timings.median()),
+        ms(timings.max())
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let timings = Timings::new("parse", [3, 1, 2, 5].map(Duration::from_millis).to_vec());
+        assert_eq!(timings.min(), Duration::from_millis(1));
+        assert_eq!(timings.median(), Duration::from_millis(3));
+        assert_eq!(timings.max(), Duration::from_millis(5));
+    }
+}
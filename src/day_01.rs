@@ -1,19 +1,107 @@
+use crate::error::Result;
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rank::{self, TopN};
+use crate::util::rng::Rng;
 
-fn elf_calories(file: &String) -> Vec<i32> {
-    return util::read_int_blocks(file)
-        .iter()
-        .map(|block| block.iter().sum::<i32>())
-        .collect();
+/// An elf and the calories of the food they carry.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Elf {
+    /// 1-based, in the order of the input.
+    pub number: usize,
+    pub items: Vec<i32>,
 }
 
-pub fn part_1(file: &String) -> i32 {
-    return *elf_calories(file).iter().max().unwrap();
+impl Elf {
+    pub fn total(&self) -> i32 {
+        self.items.iter().sum()
+    }
 }
 
-pub fn part_2(file: &String) -> i32 {
-    let mut cals = elf_calories(file);
-    cals.sort();
-    cals.reverse();
-    return cals.iter().take(3).cloned().sum();
+/// The elves of an input, read one block at a time.
+pub fn elves(file: &str) -> Result<impl Iterator<Item = Result<Elf>>> {
+    Ok(util::blocks(file)?.enumerate().map(|(i, block)| {
+        Ok(Elf {
+            number: i + 1,
+            items: block?.parse(|lines| util::parse_lines(lines, util::parse_token))?,
+        })
+    }))
+}
+
+/// The `k` elves carrying the most calories, the one with the most first.
+pub fn top_elves(file: &str, k: usize) -> Result<Vec<Elf>> {
+    let mut top = TopN::new(k);
+    for elf in elves(file)? {
+        let elf = elf?;
+        top.push(elf.total(), elf);
+    }
+    Ok(top.into_sorted_vec())
+}
+
+/// The elves carrying more than `calories`, in the order of the input.
+pub fn elves_above(file: &str, calories: i32) -> Result<Vec<Elf>> {
+    elves(file)?
+        .filter(|elf| elf.as_ref().map_or(true, |elf| elf.total() > calories))
+        .collect()
+}
+
+fn top_total(elf_calories: &[i32], k: usize) -> i32 {
+    rank::top_n_by_key(elf_calories, k, |calories| **calories)
+        .into_iter()
+        .map(|(_, calories)| calories)
+        .sum()
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    type Input = Vec<i32>;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        elves(file)?.map(|elf| elf.map(|elf| elf.total())).collect()
+    }
+
+    fn part_1(elf_calories: &Self::Input, _: &()) -> Answer {
+        top_total(elf_calories, 1).into()
+    }
+
+    fn part_2(elf_calories: &Self::Input, _: &()) -> Answer {
+        top_total(elf_calories, 3).into()
+    }
+
+    fn generate(rng: &mut Rng, elves: usize) -> Option<String> {
+        let elves: Vec<String> = (0..elves)
+            .map(|_| {
+                (0..rng.range(1..16))
+                    .map(|_| format!("{}\n", rng.range(1000..60000)))
+                    .collect()
+            })
+            .collect();
+        Some(elves.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(elves: &[Elf]) -> Vec<usize> {
+        elves.iter().map(|elf| elf.number).collect()
+    }
+
+    #[test]
+    fn test_queries() {
+        assert_eq!(numbers(&top_elves("01-test", 3).unwrap()), [4, 3, 5]);
+        assert_eq!(numbers(&elves_above("01-test", 10000).unwrap()), [3, 4]);
+        let elves: Vec<Elf> = elves("01-test").unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(elves.len(), 5);
+        assert_eq!(
+            elves[2],
+            Elf {
+                number: 3,
+                items: vec![5000, 6000]
+            }
+        );
+    }
 }
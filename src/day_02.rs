@@ -1,114 +1,538 @@
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
+use crate::util::Block;
 
-#[derive(Copy, Clone)]
-enum Shape {
-    Rock = 0,
-    Paper = 1,
-    Scissors = 2,
-}
+/// The rules of the puzzle, in the format of [`Game::parse`].
+const CLASSIC: &str = "\
+Rock 1 A X
+Paper 2 B Y
+Scissors 3 C Z
 
-use Shape::*;
+Rock beats Scissors
+Paper beats Rock
+Scissors beats Paper
 
-fn int_to_shape(i: i32) -> Shape {
-    match i {
-        0 => Rock,
-        1 => Paper,
-        2 => Scissors,
-        _ => panic!("Invalid shape"),
-    }
-}
+lose 0 X
+draw 3 Y
+win 6 Z
+";
 
-#[derive(Copy, Clone, Debug)]
-enum Res {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
     Lose,
     Draw,
     Win,
 }
 
-use Res::*;
+use Outcome::*;
+
+impl Outcome {
+    fn parse(s: &str) -> Result<Self> {
+        match s {
+            "lose" => Ok(Lose),
+            "draw" => Ok(Draw),
+            "win" => Ok(Win),
+            _ => Err(Error::invalid("Invalid outcome", s)),
+        }
+    }
 
-fn parse_opp_shape(opp_char: &str) -> Shape {
-    match opp_char {
-        "A" => Rock,
-        "B" => Paper,
-        "C" => Scissors,
-        _ => panic!("Invalid opponent shape"),
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lose => "lose",
+            Draw => "draw",
+            Win => "win",
+        }
     }
 }
 
-fn parse_my_shape(my_char: &str) -> Shape {
-    match my_char {
-        "X" => Rock,
-        "Y" => Paper,
-        "Z" => Scissors,
-        _ => panic!("Invalid opponent shape"),
+#[derive(Clone, Debug)]
+pub struct Shape {
+    pub name: String,
+    pub score: i32,
+    /// The letter of the shape in the first column of a strategy guide.
+    pub opponent_letter: char,
+    /// The letter of the shape in the second column, read as the shape to play.
+    pub letter: char,
+}
+
+/// The rules of a game like rock paper scissors: its shapes, which of them beats which and
+/// what every round scores.
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub shapes: Vec<Shape>,
+    /// `beats[a][b]` if shape `a` beats shape `b`. Shapes that don't beat each other draw.
+    beats: Vec<Vec<bool>>,
+    /// The score and the second column letter of losing, drawing and winning.
+    outcomes: [(i32, char); 3],
+}
+
+fn letter(token: &str) -> Result<char> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(Error::invalid("Expected a single letter", token)),
     }
 }
 
-fn parse_result(result_char: &str) -> Res {
-    match result_char {
-        "X" => Lose,
-        "Y" => Draw,
-        "Z" => Win,
-        _ => panic!("Invalid result"),
+impl Game {
+    /// The rules of the puzzle: rock, paper and scissors encoded as A, B and C by the
+    /// opponent and as X, Y and Z, which also stand for losing, drawing and winning.
+    pub fn classic() -> Self {
+        let lines = CLASSIC.lines().map(str::to_string).collect();
+        Self::from_blocks(&util::lines_to_blocks(lines)).expect("Invalid classic rules")
+    }
+
+    /// Reads rules in three blocks. The shapes come first, one per line with its name,
+    /// score and letters for the two columns of a guide, then lines like
+    /// `Rock beats Scissors Lizard` and last the score and letter of every outcome, e.g.
+    /// `win 6 Z`.
+    pub fn parse(file: &str) -> Result<Self> {
+        Self::from_blocks(&util::read_blocks(file)?)
+    }
+
+    fn from_blocks(blocks: &[Block]) -> Result<Self> {
+        let shapes = util::block(blocks, 0)?.parse(|lines| {
+            util::parse_lines(lines, |line| {
+                let tokens = util::tokenize(line);
+                Ok(Shape {
+                    name: util::token(&tokens, 0)?.to_string(),
+                    score: util::parse_token(util::token(&tokens, 1)?)?,
+                    opponent_letter: letter(util::token(&tokens, 2)?)?,
+                    letter: letter(util::token(&tokens, 3)?)?,
+                })
+            })
+        })?;
+        for (i, shape) in shapes.iter().enumerate() {
+            if shapes[..i].iter().any(|other| {
+                other.name == shape.name
+                    || other.opponent_letter == shape.opponent_letter
+                    || other.letter == shape.letter
+            }) {
+                let err = Error::invalid("Duplicate shape name or letter", &shape.name);
+                return Err(err.at_line(blocks[0].start + i + 1));
+            }
+        }
+        let index = |name: &str| {
+            shapes
+                .iter()
+                .position(|shape| shape.name == name)
+                .ok_or_else(|| Error::invalid("Unknown shape", name))
+        };
+
+        let mut beats = vec![vec![false; shapes.len()]; shapes.len()];
+        util::block(blocks, 1)?.parse(|lines| {
+            util::parse_lines(lines, |line| {
+                let tokens = util::tokenize(line);
+                let winner = index(util::token(&tokens, 0)?)?;
+                if util::token(&tokens, 1)? != "beats" {
+                    return Err(Error::invalid("Expected beats", &tokens[1]));
+                }
+                util::token(&tokens, 2)?;
+                for loser in &tokens[2..] {
+                    let loser = index(loser)?;
+                    if winner == loser || beats[loser][winner] {
+                        return Err(Error::invalid("Contradicting rule", line));
+                    }
+                    beats[winner][loser] = true;
+                }
+                Ok(())
+            })
+        })?;
+
+        let mut outcomes = [None; 3];
+        util::block(blocks, 2)?.parse(|lines| {
+            util::parse_lines(lines, |line| {
+                let tokens = util::tokenize(line);
+                let name = util::token(&tokens, 0)?;
+                let outcome = Outcome::parse(name)?;
+                let score = util::parse_token(util::token(&tokens, 1)?)?;
+                let letter = letter(util::token(&tokens, 2)?)?;
+                if outcomes[outcome as usize].is_some()
+                    || outcomes.iter().flatten().any(|(_, other)| *other == letter)
+                {
+                    return Err(Error::invalid("Duplicate outcome or letter", name));
+                }
+                outcomes[outcome as usize] = Some((score, letter));
+                Ok(())
+            })
+        })?;
+        let [Some(lose), Some(draw), Some(win)] = outcomes else {
+            return Err(
+                Error::new("Expected a score for losing, drawing and winning")
+                    .at_line(blocks[2].start + 1),
+            );
+        };
+        let game = Self {
+            shapes,
+            beats,
+            outcomes: [lose, draw, win],
+        };
+
+        for (opponent, shape) in game.shapes.iter().enumerate() {
+            for outcome in [Lose, Draw, Win] {
+                if game.shape_for(opponent, outcome).is_none() {
+                    return Err(Error::new(format!(
+                        "No shape to {} against {}",
+                        outcome.as_str(),
+                        shape.name
+                    )));
+                }
+            }
+        }
+        Ok(game)
+    }
+
+    pub fn outcome(&self, me: usize, opponent: usize) -> Outcome {
+        if self.beats[me][opponent] {
+            Win
+        } else if self.beats[opponent][me] {
+            Lose
+        } else {
+            Draw
+        }
+    }
+
+    pub fn score(&self, me: usize, opponent: usize) -> i32 {
+        self.shapes[me].score + self.outcomes[self.outcome(me, opponent) as usize].0
+    }
+
+    /// The best scoring shape with the given outcome.
+    fn shape_for(&self, opponent: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.shapes.len())
+            .filter(|me| self.outcome(*me, opponent) == outcome)
+            .max_by_key(|me| (self.shapes[*me].score, std::cmp::Reverse(*me)))
     }
 }
 
-fn score_1(opp_shape: Shape, my_shape: Shape) -> i32 {
-    let shape_score = my_shape as i32 + 1;
-    let diff = (3 + my_shape as i32 - opp_shape as i32) % 3;
-    let win_score = match diff {
-        0 => 3,
-        1 => 6,
-        _ => 0,
-    };
-    shape_score + win_score
+/// One round of a strategy guide as it's played.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Round {
+    pub opponent: usize,
+    pub me: usize,
+    pub outcome: Outcome,
+    pub score: i32,
 }
 
-fn score_2(opp_shape: Shape, result: Res) -> i32 {
-    let my_shape: Shape = int_to_shape(match result {
-        Lose => (opp_shape as i32 + 2) % 3,
-        Draw => opp_shape as i32,
-        Win => (opp_shape as i32 + 1) % 3,
-    });
-    let res = score_1(opp_shape, my_shape);
-    res
+/// Which shape every letter of the second column stands for, with the total score.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Interpretation {
+    pub shapes: Vec<(char, usize)>,
+    pub score: i32,
 }
 
-fn parse_strategy_1(file: &String) -> Vec<(Shape, Shape)> {
-    util::read_lines(file)
-        .iter()
-        .map(|line| {
-            let tokens = util::tokenize(line);
-            let opp_shape = parse_opp_shape(&tokens[0]);
-            let my_shape = parse_my_shape(&tokens[1]);
-            (opp_shape, my_shape)
-        })
-        .collect()
+/// A strategy guide: the shape of the opponent and a letter per round.
+pub struct Guide {
+    pub game: Game,
+    rounds: Vec<(usize, char)>,
 }
 
-fn parse_strategy_2(file: &String) -> Vec<(Shape, Res)> {
-    util::read_lines(file)
-        .iter()
-        .map(|line| {
+impl Guide {
+    pub fn parse(game: Game, file: &str) -> Result<Self> {
+        let rounds = util::lines(file)?.parse(|line| {
             let tokens = util::tokenize(line);
-            let opp_shape = parse_opp_shape(&tokens[0]);
-            let res = parse_result(&tokens[1]);
-            (opp_shape, res)
+            let opponent_letter = letter(util::token(&tokens, 0)?)?;
+            let opponent = game
+                .shapes
+                .iter()
+                .position(|shape| shape.opponent_letter == opponent_letter)
+                .ok_or_else(|| Error::invalid("Invalid opponent shape", &tokens[0]))?;
+            let code = letter(util::token(&tokens, 1)?)?;
+            let is_shape = game.shapes.iter().any(|shape| shape.letter == code);
+            if !is_shape && game.outcomes.iter().all(|(_, letter)| *letter != code) {
+                return Err(Error::invalid("Invalid code", &tokens[1]));
+            }
+            Ok((opponent, code))
+        })?;
+        Ok(Self { game, rounds })
+    }
+
+    fn round(&self, opponent: usize, me: usize) -> Round {
+        Round {
+            opponent,
+            me,
+            outcome: self.game.outcome(me, opponent),
+            score: self.game.score(me, opponent),
+        }
+    }
+
+    /// The rounds when the second column is the shape to play.
+    pub fn rounds_by_shape(&self) -> Result<Vec<Round>> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .map(|(i, (opponent, code))| {
+                let me = self
+                    .game
+                    .shapes
+                    .iter()
+                    .position(|shape| shape.letter == *code)
+                    .ok_or_else(|| {
+                        Error::invalid("Not a shape", code.to_string()).at_line(i + 1)
+                    })?;
+                Ok(self.round(*opponent, me))
+            })
+            .collect()
+    }
+
+    /// The rounds when the second column is how the round has to end.
+    pub fn rounds_by_outcome(&self) -> Result<Vec<Round>> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .map(|(i, (opponent, code))| {
+                let outcome = [Lose, Draw, Win]
+                    .into_iter()
+                    .find(|outcome| self.game.outcomes[*outcome as usize].1 == *code)
+                    .ok_or_else(|| {
+                        Error::invalid("Not an outcome", code.to_string()).at_line(i + 1)
+                    })?;
+                let me = self.game.shape_for(*opponent, outcome).unwrap();
+                Ok(self.round(*opponent, me))
+            })
+            .collect()
+    }
+
+    /// The shapes to read the letters of the second column as for the highest score, every
+    /// letter standing for a different shape. `None` if there are more letters than shapes.
+    pub fn best_interpretation(&self) -> Option<Interpretation> {
+        let mut codes: Vec<char> = self.rounds.iter().map(|(_, code)| *code).collect();
+        codes.sort();
+        codes.dedup();
+        let shapes = self.game.shapes.len();
+        let mut counts = vec![vec![0; shapes]; codes.len()];
+        for (opponent, code) in &self.rounds {
+            counts[codes.binary_search(code).unwrap()][*opponent] += 1;
+        }
+        // The score of every letter as every shape.
+        let scores: Vec<Vec<i32>> = counts
+            .iter()
+            .map(|counts| {
+                (0..shapes)
+                    .map(|me| {
+                        (0..shapes)
+                            .map(|opponent| counts[opponent] * self.game.score(me, opponent))
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+        let chosen = assign(&scores)?;
+        let score = chosen
+            .iter()
+            .zip(&scores)
+            .map(|(shape, scores)| scores[*shape])
+            .sum();
+        Some(Interpretation {
+            shapes: codes.into_iter().zip(chosen).collect(),
+            score,
         })
-        .collect()
+    }
+}
+
+/// The shape for every letter that scores the most in total, each letter getting a
+/// different shape, or `None` if there are more letters than shapes. This is the Hungarian
+/// method on the negated scores: every letter is added along the cheapest path of
+/// reassignments, keeping potentials for letters and shapes so that the reduced costs stay
+/// non-negative. That takes O(letters² × shapes) rather than trying every permutation.
+fn assign(scores: &[Vec<i32>]) -> Option<Vec<usize>> {
+    let letters = scores.len();
+    let shapes = scores.first().map_or(0, Vec::len);
+    if letters > shapes {
+        return None;
+    }
+    // 1-based, with letter and shape 0 standing for none.
+    let cost = |letter: usize, shape: usize| -i64::from(scores[letter - 1][shape - 1]);
+    let mut letter_potential = vec![0; letters + 1];
+    let mut shape_potential = vec![0; shapes + 1];
+    let mut letter_of = vec![0; shapes + 1];
+    let mut previous = vec![0; shapes + 1];
+    for letter in 1..=letters {
+        letter_of[0] = letter;
+        let mut shape = 0;
+        let mut min_reduced = vec![i64::MAX; shapes + 1];
+        let mut visited = vec![false; shapes + 1];
+        // Grow a tree of tight edges until it reaches a free shape.
+        while letter_of[shape] != 0 {
+            visited[shape] = true;
+            let current = letter_of[shape];
+            let mut delta = i64::MAX;
+            let mut next = 0;
+            for other in 1..=shapes {
+                if visited[other] {
+                    continue;
+                }
+                let reduced =
+                    cost(current, other) - letter_potential[current] - shape_potential[other];
+                if reduced < min_reduced[other] {
+                    min_reduced[other] = reduced;
+                    previous[other] = shape;
+                }
+                if min_reduced[other] < delta {
+                    delta = min_reduced[other];
+                    next = other;
+                }
+            }
+            for other in 0..=shapes {
+                if visited[other] {
+                    letter_potential[letter_of[other]] += delta;
+                    shape_potential[other] -= delta;
+                } else {
+                    min_reduced[other] -= delta;
+                }
+            }
+            shape = next;
+        }
+        // Shift the letters along the path back to the new one.
+        while shape != 0 {
+            let before = previous[shape];
+            letter_of[shape] = letter_of[before];
+            shape = before;
+        }
+    }
+    let mut chosen = vec![0; letters];
+    for (shape, letter) in letter_of.iter().enumerate().skip(1) {
+        if *letter != 0 {
+            chosen[letter - 1] = shape - 1;
+        }
+    }
+    Some(chosen)
 }
 
-pub fn part_1(file: &String) -> i32 {
-    parse_strategy_1(file)
-        .iter()
-        .map(|(opp, my)| score_1(*opp, *my))
-        .sum()
+fn total(rounds: &[Round]) -> i32 {
+    rounds.iter().map(|round| round.score).sum()
+}
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    type Input = Guide;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Guide::parse(Game::classic(), file)
+    }
+
+    fn part_1(guide: &Self::Input, _: &()) -> Answer {
+        let rounds = guide.rounds_by_shape();
+        total(&rounds.expect("Every classic letter is a shape")).into()
+    }
+
+    fn part_2(guide: &Self::Input, _: &()) -> Answer {
+        let rounds = guide.rounds_by_outcome();
+        total(&rounds.expect("Every classic letter is an outcome")).into()
+    }
+
+    fn generate(rng: &mut Rng, rounds: usize) -> Option<String> {
+        Some(
+            (0..rounds)
+                .map(|_| {
+                    format!(
+                        "{} {}\n",
+                        rng.choose(&['A', 'B', 'C']),
+                        rng.choose(&['X', 'Y', 'Z'])
+                    )
+                })
+                .collect(),
+        )
+    }
 }
 
-pub fn part_2(file: &String) -> i32 {
-    parse_strategy_2(file)
-        .iter()
-        .map(|(opp, res)| score_2(*opp, *res))
-        .sum()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(rules: &str) -> Result<Game> {
+        let lines = rules.lines().map(str::to_string).collect();
+        Game::from_blocks(&util::lines_to_blocks(lines))
+    }
+
+    #[test]
+    fn test_best_interpretation() {
+        let guide = Guide::parse(Game::classic(), "02-test").unwrap();
+        let best = guide.best_interpretation().unwrap();
+        assert_eq!(best.shapes, [('X', 2), ('Y', 1), ('Z', 0)]);
+        assert_eq!(best.score, 24);
+        assert!(best.score >= total(&guide.rounds_by_shape().unwrap()));
+    }
+
+    /// The best total score of giving every letter a different shape, trying them all.
+    fn best_total(scores: &[Vec<i32>], taken: &mut Vec<usize>) -> Option<i32> {
+        let Some(letter_scores) = scores.get(taken.len()) else {
+            return Some(0);
+        };
+        (0..letter_scores.len())
+            .filter_map(|shape| {
+                if taken.contains(&shape) {
+                    return None;
+                }
+                taken.push(shape);
+                let total = best_total(scores, taken).map(|total| total + letter_scores[shape]);
+                taken.pop();
+                total
+            })
+            .max()
+    }
+
+    #[test]
+    fn test_assign_against_brute_force() {
+        let mut rng = Rng::new(2);
+        for _ in 0..500 {
+            let letters = rng.range(1..5) as usize;
+            let shapes = rng.range(1..7) as usize;
+            let scores: Vec<Vec<i32>> = (0..letters)
+                .map(|_| (0..shapes).map(|_| rng.range(-20..50) as i32).collect())
+                .collect();
+            let expected = best_total(&scores, &mut Vec::new());
+            let chosen = assign(&scores);
+            assert_eq!(chosen.is_some(), expected.is_some(), "{:?}", scores);
+            if let Some(chosen) = chosen {
+                let mut distinct = chosen.clone();
+                distinct.sort();
+                distinct.dedup();
+                assert_eq!(distinct.len(), letters);
+                let total: i32 = chosen.iter().zip(&scores).map(|(shape, s)| s[*shape]).sum();
+                assert_eq!(Some(total), expected, "{:?}", scores);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rpsls() {
+        let game = Game::parse("02-rpsls").unwrap();
+        for me in 0..5 {
+            let outcomes: Vec<Outcome> = (0..5).map(|other| game.outcome(me, other)).collect();
+            assert_eq!(
+                outcomes.iter().filter(|outcome| **outcome == Win).count(),
+                2
+            );
+            assert_eq!(
+                outcomes.iter().filter(|outcome| **outcome == Lose).count(),
+                2
+            );
+        }
+        let guide = Guide::parse(game, "02-test").unwrap();
+        assert_eq!(total(&guide.rounds_by_shape().unwrap()), 24);
+        assert_eq!(total(&guide.rounds_by_outcome().unwrap()), 20);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let err =
+            game("Rock 1 A X\nPaper 2 B Y\n\nRock beats Paper\nPaper beats Rock\n\n").unwrap_err();
+        assert_eq!(err.line, Some(5));
+        let err = game("Rock 1 A X\nPaper 2 B Y\n\nPaper beats Rock\n\nlose 0 X\ndraw 3 Y\n")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "6: Expected a score for losing, drawing and winning"
+        );
+        let rules = "Rock 1 A X\nPaper 2 B Y\n\nPaper beats Rock\n\n";
+        for outcomes in ["lose 0 X\nlose 1 Y\n", "lose 0 X\ndraw 3 Z\nwin 6 Z\n"] {
+            let err = game(&format!("{}{}", rules, outcomes)).unwrap_err();
+            assert_eq!(err.message, "Duplicate outcome or letter");
+            assert_eq!(err.line, Some(outcomes.lines().count() + 5));
+        }
+    }
 }
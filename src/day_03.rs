@@ -1,55 +1,228 @@
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
-use std::collections::HashSet;
-
-fn find_duplicate(line: &String) -> char {
-    let half_len = line.len() >> 1;
-    let first_half = &line[..half_len];
-    let second_half = &line[half_len..];
-    let first_item_set = first_half.chars().collect::<HashSet<char>>();
-    let second_item_set = second_half.chars().collect::<HashSet<char>>();
-    *first_item_set
-        .intersection(&second_item_set)
-        .next()
-        .unwrap()
-}
-fn char_prio(c: char) -> i32 {
-    let char_code = c as u8;
-    match char_code {
-        b'a'..=b'z' => 1 + ((char_code - b'a') as i32),
-        b'A'..=b'Z' => 27 + ((char_code - b'A') as i32),
-        _ => panic!("Invalid char: {}", c),
-    }
-}
-
-pub fn part_1(file: &String) -> i32 {
-    util::read_lines(file)
-        .iter()
-        .map(find_duplicate)
-        .map(char_prio)
-        .sum()
-}
-
-fn find_shared_item(line_1: &String, line_2: &String, line_3: &String) -> char {
-    let line_1_set = line_1.chars().collect::<HashSet<char>>();
-    let line_2_set = line_2.chars().collect::<HashSet<char>>();
-    let line_3_set = line_3.chars().collect::<HashSet<char>>();
-    *line_1_set
-        .intersection(&line_2_set)
-        .find(|c| line_3_set.contains(c))
-        .unwrap()
-}
-
-pub fn part_2(file: &String) -> i32 {
-    let lines = util::read_lines(file);
-    let mut i = 0;
-    let mut sum = 0;
-    while i < lines.len() {
-        let line_1 = &lines[i];
-        let line_2 = &lines[i + 1];
-        let line_3 = &lines[i + 2];
-        let shared_item = find_shared_item(line_1, line_2, line_3);
-        sum += char_prio(shared_item);
-        i += 3;
-    }
-    sum
+use crate::util::rng::Rng;
+
+/// The compartments of a rucksack in the puzzle.
+const COMPARTMENTS: usize = 2;
+
+/// The elves of a group in the puzzle.
+const GROUP_SIZE: usize = 3;
+
+/// The priority of an item: 1 to 26 for `a` to `z` and 27 to 52 for `A` to `Z`.
+pub fn priority(item: char) -> Option<u32> {
+    match item {
+        'a'..='z' => Some(item as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Some(item as u32 - 'A' as u32 + 27),
+        _ => None,
+    }
+}
+
+fn item(priority: u32) -> char {
+    let base = if priority <= 26 { b'a' } else { b'A' - 26 };
+    char::from(base + priority as u8 - 1)
+}
+
+/// A set of items, with bit `priority - 1` set for every item in it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Items(u64);
+
+impl Items {
+    pub const ALL: Self = Self((1 << 52) - 1);
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut items = Self::default();
+        for (i, c) in s.chars().enumerate() {
+            let priority =
+                priority(c).ok_or_else(|| Error::invalid("Invalid item", c).at_column(i + 1))?;
+            items.0 |= 1 << (priority - 1);
+        }
+        Ok(items)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// The items, lowest priority first.
+    pub fn iter(self) -> impl Iterator<Item = char> {
+        (1..=52)
+            .filter(move |priority| self.0 & (1 << (priority - 1)) != 0)
+            .map(item)
+    }
+
+    pub fn priority_sum(self) -> u32 {
+        self.iter().filter_map(priority).sum()
+    }
+}
+
+/// A rucksack with its items split evenly between compartments.
+#[derive(Debug)]
+pub struct Rucksack {
+    pub compartments: Vec<Items>,
+}
+
+impl Rucksack {
+    pub fn parse(line: &str, compartments: usize) -> Result<Self> {
+        if compartments == 0 {
+            return Err(Error::new("Need at least one compartment"));
+        }
+        if !line.len().is_multiple_of(compartments) {
+            return Err(Error::invalid(
+                format!("Expected a length divisible by {}", compartments),
+                line,
+            ));
+        }
+        // Checks the items before splitting the line, which only has ASCII letters then.
+        Items::parse(line)?;
+        let len = line.len() / compartments;
+        let compartments = (0..compartments)
+            .map(|i| Items::parse(&line[i * len..(i + 1) * len]))
+            .collect::<Result<_>>()?;
+        Ok(Self { compartments })
+    }
+
+    /// The items in every compartment.
+    pub fn shared(&self) -> Items {
+        self.compartments
+            .iter()
+            .fold(Items::ALL, |shared, items| shared.intersection(*items))
+    }
+
+    pub fn items(&self) -> Items {
+        self.compartments
+            .iter()
+            .fold(Items::default(), |all, items| all.union(*items))
+    }
+}
+
+/// The items every rucksack of a group carries.
+pub fn badges(group: &[Rucksack]) -> Items {
+    group.iter().fold(Items::ALL, |shared, rucksack| {
+        shared.intersection(rucksack.items())
+    })
+}
+
+/// Reads rucksacks of `compartments` each, failing if they can't be split into groups of
+/// `group_size`.
+pub fn parse_rucksacks(
+    file: &str,
+    compartments: usize,
+    group_size: usize,
+) -> Result<Vec<Rucksack>> {
+    if compartments == 0 {
+        return Err(Error::new("Need at least one compartment"));
+    }
+    if group_size == 0 {
+        return Err(Error::new("Need at least one group member"));
+    }
+    let rucksacks = util::lines(file)?.parse(|line| Rucksack::parse(line, compartments))?;
+    let incomplete = rucksacks.len() % group_size;
+    if incomplete != 0 {
+        let start = rucksacks.len() - incomplete;
+        return Err(Error::new(format!(
+            "Expected groups of {} rucksacks, the last one has {}",
+            group_size, incomplete
+        ))
+        .at_line(start + 1));
+    }
+    Ok(rucksacks)
+}
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    type Input = Vec<Rucksack>;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        parse_rucksacks(file, COMPARTMENTS, GROUP_SIZE)
+    }
+
+    fn part_1(rucksacks: &Self::Input, _: &()) -> Answer {
+        rucksacks
+            .iter()
+            .map(|rucksack| rucksack.shared().priority_sum())
+            .sum::<u32>()
+            .into()
+    }
+
+    fn part_2(rucksacks: &Self::Input, _: &()) -> Answer {
+        rucksacks
+            .chunks(GROUP_SIZE)
+            .map(|group| badges(group).priority_sum())
+            .sum::<u32>()
+            .into()
+    }
+
+    /// Groups of three rucksacks. Every rucksack gets its own letters besides the badge of
+    /// its group, split between the compartments so that only one of them is in both.
+    fn generate(rng: &mut Rng, rucksacks: usize) -> Option<String> {
+        let mut letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+        let mut res = String::new();
+        for _ in 0..rucksacks.div_ceil(3) {
+            rng.shuffle(&mut letters);
+            let (badge, pools) = letters.split_first().unwrap();
+            for pool in pools.chunks(pools.len() / 3).take(3) {
+                let (duplicate, pool) = pool.split_first().unwrap();
+                let (left_pool, right_pool) = pool.split_at(pool.len() / 2);
+                let half_len = rng.range(2..17) as usize;
+                let mut halves = [vec![*duplicate], vec![*duplicate]];
+                halves[rng.index(2)].push(*badge);
+                for (half, pool) in halves.iter_mut().zip([left_pool, right_pool]) {
+                    while half.len() < half_len {
+                        half.push(*rng.choose(pool));
+                    }
+                    rng.shuffle(half);
+                    res.extend(half.iter());
+                }
+                res.push('\n');
+            }
+        }
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items() {
+        let items = Items::parse("bZaab").unwrap();
+        assert_eq!(items.iter().collect::<String>(), "abZ");
+        assert_eq!(items.priority_sum(), 1 + 2 + 52);
+        let err = Items::parse("ab1").unwrap_err();
+        assert_eq!(err.column, Some(3));
+    }
+
+    #[test]
+    fn test_compartments() {
+        let rucksack = Rucksack::parse("abXbcXbdX", 3).unwrap();
+        assert_eq!(rucksack.shared().iter().collect::<String>(), "bX");
+        assert_eq!(rucksack.items().iter().collect::<String>(), "abcdX");
+        assert!(Rucksack::parse("abc", 2).is_err());
+        assert_eq!(Rucksack::parse("ab-d", 2).unwrap_err().column, Some(3));
+        let err = Rucksack::parse("ab", 0).unwrap_err();
+        assert_eq!(err.message, "Need at least one compartment");
+    }
+
+    #[test]
+    fn test_groups() {
+        let rucksacks = parse_rucksacks("03-test", COMPARTMENTS, 2).unwrap();
+        let badges: Vec<String> = rucksacks
+            .chunks(2)
+            .map(|group| badges(group).iter().collect())
+            .collect();
+        assert_eq!(badges, ["frsFM", "qvwBT", "GJZ"]);
+        let err = parse_rucksacks("03-test", COMPARTMENTS, 4).unwrap_err();
+        assert_eq!(err.line, Some(5));
+        let err = parse_rucksacks("03-test", COMPARTMENTS, 0).unwrap_err();
+        assert_eq!(err.message, "Need at least one group member");
+        assert!(parse_rucksacks("03-test", 0, 3).is_err());
+    }
 }
@@ -1,44 +1,108 @@
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::interval::Interval;
+use crate::util::rng::Rng;
 
-type Assign = std::ops::RangeInclusive<i32>;
+type Assign = Interval<i32>;
 
-fn parse_assignment(assignment: &str) -> Assign {
-    let tokens: Vec<i32> = assignment
-        .split("-")
-        .map(|s| s.parse::<i32>().unwrap())
-        .collect();
-    tokens[0]..=tokens[1]
+fn parse_assignment(assignment: &str) -> Result<Assign> {
+    let (start, end) = assignment
+        .split_once('-')
+        .ok_or_else(|| Error::invalid("Expected a range like 2-4", assignment))?;
+    Interval::new(util::parse_token(start)?, util::parse_token(end)?).ok_or_else(|| {
+        Error::invalid(
+            "Expected a range that doesn't end before it starts",
+            assignment,
+        )
+    })
 }
 
-fn parse_assignments(line: &String) -> (Assign, Assign) {
-    let tokens: Vec<&str> = line.split(",").collect();
-    (parse_assignment(tokens[0]), parse_assignment(tokens[1]))
-}
-
-fn assign_contains(assign: &Assign, sub_assign: &Assign) -> bool {
-    assign.start() <= sub_assign.start() && assign.end() <= sub_assign.end()
+fn parse_assignments(line: &str) -> Result<(Assign, Assign)> {
+    let (first, second) = line
+        .split_once(',')
+        .ok_or_else(|| Error::invalid("Expected two assignments", line))?;
+    Ok((parse_assignment(first)?, parse_assignment(second)?))
 }
 
 fn one_contains_another(assign_1: &Assign, assign_2: &Assign) -> bool {
-    assign_contains(assign_1, assign_2) || assign_contains(assign_2, assign_1)
+    assign_1.contains_interval(assign_2) || assign_2.contains_interval(assign_1)
 }
 
-fn assignments_overlap(assign_1: &Assign, assign_2: &Assign) -> bool {
-    assign_1.start() <= assign_2.end() && assign_2.start() <= assign_1.end()
-}
+pub struct Day04;
+
+impl Solution for Day04 {
+    type Input = Vec<(Assign, Assign)>;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        util::lines(file)?.parse(parse_assignments)
+    }
 
-pub fn part_1(file: &str) -> i32 {
-    util::read_lines(file)
-        .iter()
-        .map(parse_assignments)
-        .filter(|(assign_1, assign_2)| one_contains_another(assign_1, assign_2))
-        .count() as i32
+    fn part_1(assignments: &Self::Input, _: &()) -> Answer {
+        assignments
+            .iter()
+            .filter(|(assign_1, assign_2)| one_contains_another(assign_1, assign_2))
+            .count()
+            .into()
+    }
+
+    fn part_2(assignments: &Self::Input, _: &()) -> Answer {
+        assignments
+            .iter()
+            .filter(|(assign_1, assign_2)| assign_1.overlaps(assign_2))
+            .count()
+            .into()
+    }
+
+    fn generate(rng: &mut Rng, pairs: usize) -> Option<String> {
+        let mut section = || {
+            let first = rng.range(1..100);
+            (first, rng.range(first..100))
+        };
+        Some(
+            (0..pairs)
+                .map(|_| {
+                    let (a, b) = section();
+                    let (c, d) = section();
+                    format!("{}-{},{}-{}\n", a, b, c, d)
+                })
+                .collect(),
+        )
+    }
 }
 
-pub fn part_2(file: &str) -> i32 {
-    util::read_lines(file)
-        .iter()
-        .map(parse_assignments)
-        .filter(|(assign_1, assign_2)| assignments_overlap(assign_1, assign_2))
-        .count() as i32
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn random_assign(rng: &mut Rng) -> Assign {
+        let first = rng.range(1..10) as i32;
+        Interval::new(first, rng.range(first as i64..10) as i32).unwrap()
+    }
+
+    #[test]
+    fn test_against_sections() {
+        let mut rng = Rng::new(4);
+        for _ in 0..1000 {
+            let assign_1 = random_assign(&mut rng);
+            let assign_2 = random_assign(&mut rng);
+            let sections_1: HashSet<i32> = (assign_1.first..=assign_1.last).collect();
+            let sections_2: HashSet<i32> = (assign_2.first..=assign_2.last).collect();
+            assert_eq!(
+                one_contains_another(&assign_1, &assign_2),
+                sections_1.is_subset(&sections_2) || sections_2.is_subset(&sections_1)
+            );
+            assert_eq!(
+                assign_1.overlaps(&assign_2),
+                !sections_1.is_disjoint(&sections_2),
+                "{:?} overlaps {:?}",
+                assign_1,
+                assign_2
+            );
+        }
+        assert!(parse_assignment("5-3").is_err());
+    }
 }
@@ -1,119 +1,330 @@
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
+
+/// A model of crane: how the crates of a step land on the stack they are moved to.
+pub trait Crane {
+    /// Puts `crates`, taken off the top of a stack with the topmost last, onto `stack`.
+    fn put(&self, crates: &[char], stack: &mut Vec<char>);
+}
+
+/// Moves one crate at a time, which reverses their order.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn put(&self, crates: &[char], stack: &mut Vec<char>) {
+        stack.extend(crates.iter().rev());
+    }
+}
+
+/// Moves all crates of a step at once, keeping their order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn put(&self, crates: &[char], stack: &mut Vec<char>) {
+        stack.extend(crates);
+    }
+}
+
+/// Moves up to `capacity` crates at once, starting with the topmost.
+pub struct LimitedCrane {
+    capacity: usize,
+}
+
+impl LimitedCrane {
+    /// The crane, failing if it can't lift a single crate.
+    pub fn new(capacity: usize) -> Result<Self> {
+        if capacity == 0 {
+            return Err(Error::new("A crane has to lift at least one crate"));
+        }
+        Ok(Self { capacity })
+    }
+}
+
+impl Crane for LimitedCrane {
+    fn put(&self, crates: &[char], stack: &mut Vec<char>) {
+        for lift in crates.rchunks(self.capacity) {
+            stack.extend(lift);
+        }
+    }
+}
 
-#[derive(Debug)]
-struct State {
-    stacks: Vec<Vec<char>>,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct State {
+    /// The crates of every stack, bottom first.
+    pub stacks: Vec<Vec<char>>,
 }
 
 impl State {
-    fn parse(lines: &Vec<String>) -> Self {
+    fn parse(lines: &[String]) -> Result<Self> {
+        let (numbers, rows) = lines
+            .split_last()
+            .ok_or_else(|| Error::new("Expected stacks"))?;
+        let number_error = |err: Error| err.locate_in(numbers).at_line(lines.len());
         let mut stacks = Vec::new();
+        for (i, token) in numbers.split_whitespace().enumerate() {
+            if util::parse_token::<usize>(token).map_err(number_error)? != i + 1 {
+                return Err(number_error(Error::invalid(
+                    format!("Expected stack {}", i + 1),
+                    token,
+                )));
+            }
+            stacks.push(Vec::new());
+        }
 
-        let boxes: Vec<String> = lines
-            .iter()
-            .rev()
-            .skip(1)
-            .map(|line|
-        // iterate over all chars in line whose position % 4 == 1
-        line.chars().enumerate().filter(|(i, _)| i % 4 == 1).map(|(_, c)| c).collect())
-            .collect();
-
-        for row in boxes {
-            for (i, c) in row.chars().enumerate() {
-                if c != ' ' {
-                    if stacks.len() == i {
-                        stacks.push(Vec::new());
+        for (row_index, row) in rows.iter().rev().enumerate() {
+            // The crates are at every fourth position, like the 'A' of "[A] ".
+            let crates = row.chars().skip(1).step_by(4).enumerate();
+            for (i, c) in crates.filter(|(_, c)| *c != ' ') {
+                let error = |message| {
+                    Error::invalid(message, c)
+                        .at_line(rows.len() - row_index)
+                        .at_column(4 * i + 2)
+                };
+                match stacks.get_mut(i) {
+                    None => return Err(error("Crate without a stack below")),
+                    Some(stack) if stack.len() < row_index => {
+                        return Err(error("Crate without a crate below"))
                     }
-                    stacks[i].push(c);
+                    Some(stack) => stack.push(c),
                 }
             }
         }
-        Self { stacks }
+        Ok(Self { stacks })
     }
 
-    fn apply_step_1(&mut self, step: &Step) {
-        for _ in 0..step.amount {
-            let item = self.stacks[step.from].pop().unwrap();
-            self.stacks[step.to].push(item)
+    /// Moves the crates of the step with the crane, failing if the stacks aren't there or
+    /// don't have enough crates.
+    pub fn apply(&mut self, crane: &dyn Crane, step: &Step) -> Result<()> {
+        for stack in [step.from, step.to] {
+            if stack >= self.stacks.len() {
+                return Err(Error::new(format!(
+                    "No stack {}, there are {}",
+                    stack + 1,
+                    self.stacks.len()
+                )));
+            }
+        }
+        let from = &mut self.stacks[step.from];
+        if from.len() < step.amount {
+            return Err(Error::new(format!(
+                "Moving {} crates from stack {}, which has {}",
+                step.amount,
+                step.from + 1,
+                from.len()
+            )));
         }
+        let crates = from.split_off(from.len() - step.amount);
+        crane.put(&crates, &mut self.stacks[step.to]);
+        Ok(())
     }
 
-    fn apply_step_2(&mut self, step: &Step) {
-        let mut tmp = Vec::new();
-        for _ in 0..step.amount {
-            tmp.push(self.stacks[step.from].pop().unwrap());
-        }
-        for item in tmp.iter().rev() {
-            self.stacks[step.to].push(*item)
-        }
+    /// The top crate of every stack that has one.
+    pub fn result(&self) -> String {
+        self.stacks.iter().filter_map(|s| s.last()).collect()
     }
+}
 
-    fn result(&self) -> String {
-        self.stacks.iter().map(|s| s.last().unwrap()).collect()
+/// The stacks in the diagram format of the puzzle input.
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let row: Vec<String> = self
+                .stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("[{}]", c),
+                    None => "   ".to_string(),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" ").trim_end())?;
+        }
+        let numbers: Vec<String> = (1..=self.stacks.len())
+            .map(|number| format!(" {} ", number))
+            .collect();
+        write!(f, "{}", numbers.join(" ").trim_end())
     }
 }
 
-#[derive(Debug)]
-struct Step {
-    amount: i32,
+#[derive(Clone, Debug)]
+pub struct Step {
+    amount: usize,
     from: usize,
     to: usize,
 }
 impl Step {
-    fn parse(line: &str) -> Self {
-        let tokens = util::tokenize(&line);
-        let amount = tokens[1].parse().unwrap();
-        let from = (tokens[3].parse::<i32>().unwrap() - 1) as usize;
-        let to = (tokens[5].parse::<i32>().unwrap() - 1) as usize;
-        Self { amount, from, to }
+    fn parse(line: &str) -> Result<Self> {
+        let tokens = util::tokenize(line);
+        let amount = util::parse_token(util::token(&tokens, 1)?)?;
+        let from = Self::parse_stack(util::token(&tokens, 3)?)?;
+        let to = Self::parse_stack(util::token(&tokens, 5)?)?;
+        Ok(Self { amount, from, to })
+    }
+
+    fn parse_stack(token: &str) -> Result<usize> {
+        match util::parse_token::<usize>(token)? {
+            0 => Err(Error::invalid("Stacks are numbered from 1", token)),
+            stack => Ok(stack - 1),
+        }
     }
 }
 
 type Steps = Vec<Step>;
 
-#[derive(Debug)]
-struct Input {
-    state: State,
-    steps: Steps,
+#[derive(Clone, Debug)]
+pub struct Input {
+    pub state: State,
+    pub steps: Steps,
+    /// The 0-based index of the first step's line in the file.
+    steps_start: usize,
 }
 
 impl Input {
-    fn parse(file: &str) -> Self {
-        let blocks = util::read_blocks(file);
-        let state_lines = &blocks[0];
-        let step_lines = &blocks[1];
-
-        let mut steps = Vec::new();
-        for line in step_lines {
-            steps.push(Step::parse(line))
-        }
-        Input {
-            state: State::parse(state_lines),
-            steps: steps,
-        }
+    /// Reads the input, failing on steps no crane can do.
+    pub fn parse(file: &str) -> Result<Self> {
+        let blocks = util::read_blocks(file)?;
+        let state_block = util::block(&blocks, 0)?;
+        let step_block = util::block(&blocks, 1)?;
+
+        let steps = step_block.parse(|lines| util::parse_lines(lines, Step::parse))?;
+        let input = Input {
+            state: state_block.parse(State::parse)?,
+            steps,
+            steps_start: step_block.start,
+        };
+        // Every crane moves as many crates between the same stacks, so one of them finds
+        // the steps that fail with any of them.
+        input.state_after(&CrateMover9001, input.steps.len())?;
+        Ok(input)
     }
 
-    fn apply_all_1(&mut self) {
-        for step in &self.steps {
-            self.state.apply_step_1(step)
+    /// The state after the first `steps` steps, with the line of the step that failed.
+    pub fn state_after(&self, crane: &dyn Crane, steps: usize) -> Result<State> {
+        let mut state = self.state.clone();
+        for (i, step) in self.steps.iter().take(steps).enumerate() {
+            state
+                .apply(crane, step)
+                .map_err(|err| err.at_line(self.steps_start + i + 1))?;
         }
+        Ok(state)
     }
 
-    fn apply_all_2(&mut self) {
-        for step in &self.steps {
-            self.state.apply_step_2(step)
-        }
+    fn result(&self, crane: &dyn Crane) -> Answer {
+        self.state_after(crane, self.steps.len())
+            .expect("Steps are checked when parsing")
+            .result()
+            .into()
     }
 }
 
-pub fn part_1(file: &str) -> String {
-    let mut input = Input::parse(file);
-    input.apply_all_1();
-    input.state.result()
+pub struct Day05;
+
+impl Solution for Day05 {
+    type Input = Input;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Input::parse(file)
+    }
+
+    fn part_1(input: &Self::Input, _: &()) -> Answer {
+        input.result(&CrateMover9000)
+    }
+
+    fn part_2(input: &Self::Input, _: &()) -> Answer {
+        input.result(&CrateMover9001)
+    }
+
+    /// Up to nine stacks, so that their numbers fit the diagram, and `steps` steps that
+    /// each move some of the crates of a stack onto another one.
+    fn generate(rng: &mut Rng, steps: usize) -> Option<String> {
+        let stacks: Vec<Vec<char>> = (0..rng.range(3..10))
+            .map(|_| {
+                (0..rng.range(1..9))
+                    .map(|_| char::from(b'A' + rng.index(26) as u8))
+                    .collect()
+            })
+            .collect();
+        let mut state = State { stacks };
+        let mut res = format!("{}\n\n", state);
+        for _ in 0..steps {
+            let from = loop {
+                let from = rng.index(state.stacks.len());
+                if !state.stacks[from].is_empty() {
+                    break from;
+                }
+            };
+            let to = (from + 1 + rng.index(state.stacks.len() - 1)) % state.stacks.len();
+            let amount = 1 + rng.index(state.stacks[from].len());
+            let step = Step { amount, from, to };
+            state
+                .apply(&CrateMover9000, &step)
+                .expect("Generated an invalid step");
+            res.push_str(&format!(
+                "move {} from {} to {}\n",
+                amount,
+                from + 1,
+                to + 1
+            ));
+        }
+        Some(res)
+    }
 }
 
-pub fn part_2(file: &str) -> String {
-    let mut input = Input::parse(file);
-    input.apply_all_2();
-    input.state.result()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let input = Input::parse("05-test").unwrap();
+        let diagram: Vec<String> = util::read_lines("05-test").unwrap()[..4].to_vec();
+        assert_eq!(input.state.to_string(), diagram.join("\n"));
+        for steps in 0..=input.steps.len() {
+            let state = input.state_after(&CrateMover9000, steps).unwrap();
+            let lines: Vec<String> = state.to_string().lines().map(str::to_string).collect();
+            assert_eq!(State::parse(&lines).unwrap(), state);
+        }
+        let last = input.state_after(&CrateMover9000, 4).unwrap();
+        assert_eq!(
+            last.to_string(),
+            "        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3"
+        );
+    }
+
+    #[test]
+    fn test_cranes() {
+        let input = Input::parse("05-input").unwrap();
+        let len = input.steps.len();
+        for (limited, crane) in [(1, &CrateMover9000 as &dyn Crane), (50, &CrateMover9001)] {
+            assert_eq!(
+                input
+                    .state_after(&LimitedCrane::new(limited).unwrap(), len)
+                    .unwrap(),
+                input.state_after(crane, len).unwrap()
+            );
+        }
+        let mut state = State::parse(&["[A]".to_string(), " 1   2".to_string()]).unwrap();
+        let crates = "abcde".chars().collect();
+        state.stacks[0] = crates;
+        let step = Step::parse("move 5 from 1 to 2").unwrap();
+        state.apply(&LimitedCrane::new(2).unwrap(), &step).unwrap();
+        assert_eq!(state.stacks[1].iter().collect::<String>(), "debca");
+        assert!(LimitedCrane::new(0).is_err());
+    }
+
+    #[test]
+    fn test_invalid_steps() {
+        let mut input = Input::parse("05-test").unwrap();
+        input.steps.push(Step::parse("move 4 from 2 to 1").unwrap());
+        let err = input.state_after(&CrateMover9000, 5).unwrap_err();
+        assert_eq!(err.message, "Moving 4 crates from stack 2, which has 1");
+        assert_eq!(err.line, Some(10));
+        input.steps[4] = Step::parse("move 1 from 4 to 1").unwrap();
+        assert!(input.state_after(&CrateMover9001, 5).is_err());
+    }
 }
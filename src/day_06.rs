@@ -1,26 +1,48 @@
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
 use std::collections::HashSet;
 
-fn detect_start_of_packet(s: &str, packet_len: usize) -> Option<usize> {
+pub fn detect_start_of_packet(s: &str, packet_len: usize) -> Option<usize> {
     for n in packet_len..=s.len() {
         let different_chars: HashSet<char> = s[n - packet_len..n].chars().collect();
         if different_chars.len() == packet_len {
             return Some(n);
         }
     }
-    return None;
+    None
 }
 
-pub fn part_1(file: &str) -> usize {
-    let lines = util::read_lines(file);
-    let s = lines[0].trim();
-    detect_start_of_packet(s, 4).unwrap()
-}
+pub struct Day06;
+
+impl Solution for Day06 {
+    type Input = String;
+    type Config = ();
 
-pub fn part_2(file: &str) -> usize {
-    let lines = util::read_lines(file);
-    let s = lines[0].trim();
-    detect_start_of_packet(s, 14).unwrap()
+    fn parse(file: &str) -> Result<Self::Input> {
+        let lines = util::read_lines(file)?;
+        let line = lines.first().ok_or_else(|| Error::new("Empty input"))?;
+        Ok(line.trim().to_string())
+    }
+
+    fn part_1(s: &Self::Input, _: &()) -> Answer {
+        detect_start_of_packet(s, 4).unwrap().into()
+    }
+
+    fn part_2(s: &Self::Input, _: &()) -> Answer {
+        detect_start_of_packet(s, 14).unwrap().into()
+    }
+
+    /// A random signal with a marker of 14 different letters near the end.
+    fn generate(rng: &mut Rng, len: usize) -> Option<String> {
+        let mut letters: Vec<char> = ('a'..='z').collect();
+        let mut signal: Vec<char> = (0..len.max(14)).map(|_| *rng.choose(&letters)).collect();
+        rng.shuffle(&mut letters);
+        let start = signal.len() - 14 - rng.index(signal.len().min(100) - 13);
+        signal[start..start + 14].copy_from_slice(&letters[..14]);
+        Some(signal.into_iter().chain(['\n']).collect())
+    }
 }
 
 #[cfg(test)]
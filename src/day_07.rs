@@ -1,6 +1,10 @@
 use std::collections::HashMap;
 
+use crate::error::{Error, Result};
+use crate::log::trace;
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
 
 #[derive(Debug)]
 struct Directory {
@@ -37,14 +41,23 @@ impl FileSystem {
         dir
     }
 
-    fn apply_cd(&mut self, dir: &str) {
+    fn apply_cd(&mut self, dir: &str) -> Result<()> {
         match dir {
             "/" => self.path = Vec::new(),
             ".." => {
-                self.path.pop().unwrap();
+                self.path
+                    .pop()
+                    .ok_or_else(|| Error::invalid("Can't leave the root directory", dir))?;
+            }
+            _ => {
+                self.current_dir()
+                    .sub_dirs
+                    .entry(dir.to_string())
+                    .or_insert_with(Directory::new);
+                self.path.push(dir.to_string())
             }
-            _ => self.path.push(dir.to_string()),
         }
+        Ok(())
     }
 
     fn add_dir(&mut self, name: &str) {
@@ -57,27 +70,28 @@ impl FileSystem {
         self.current_dir().file_sizes.insert(name.to_string(), size);
     }
 
-    fn apply_line(&mut self, line: &str) {
+    fn apply_line(&mut self, line: &str) -> Result<()> {
         let tokens: Vec<&str> = line.split_whitespace().collect();
-        match tokens[0] {
+        match util::token(&tokens, 0)? {
             "$" => {
-                if tokens[1] == "cd" {
-                    self.apply_cd(tokens[2])
+                if util::token(&tokens, 1)? == "cd" {
+                    self.apply_cd(util::token(&tokens, 2)?)?
                 }
             }
-            "dir" => self.add_dir(tokens[1]),
-            size_str => self.add_file(tokens[1], size_str.parse().unwrap()),
+            "dir" => self.add_dir(util::token(&tokens, 1)?),
+            size_str => self.add_file(util::token(&tokens, 1)?, util::parse_token(size_str)?),
         }
+        Ok(())
     }
 
     fn list_total_sizes(&self) -> Vec<usize> {
         let mut res = Vec::new();
         fn rec_list(dir: &Directory, res: &mut Vec<usize>) -> usize {
-            let file_sum: usize = dir.file_sizes.iter().map(|(_, size)| size).sum();
+            let file_sum: usize = dir.file_sizes.values().sum();
             let dir_sum: usize = dir
                 .sub_dirs
-                .iter()
-                .map(|(_, sub_dir)| rec_list(sub_dir, res))
+                .values()
+                .map(|sub_dir| rec_list(sub_dir, res))
                 .sum();
             let sum = file_sum + dir_sum;
             res.push(sum);
@@ -88,31 +102,90 @@ impl FileSystem {
     }
 }
 
-pub fn part_1(file: &str) -> usize {
-    let mut file_system = FileSystem::new();
-    for line in util::read_lines(file) {
-        file_system.apply_line(&line);
+pub struct Day07;
+
+impl Solution for Day07 {
+    /// The total size of every directory, the root directory coming last.
+    type Input = Vec<usize>;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        let mut file_system = FileSystem::new();
+        util::lines(file)?.parse(|line| file_system.apply_line(line))?;
+        trace!("File system: {:#?}", file_system.root);
+        Ok(file_system.list_total_sizes())
     }
-    //println!("File Sys: {:#?}", file_system.root);
-    let sizes = file_system.list_total_sizes();
-    sizes.iter().filter(|size| **size < (100000 as usize)).sum()
-}
 
-pub fn part_2(file: &str) -> usize {
-    let mut file_system = FileSystem::new();
-    for line in util::read_lines(file) {
-        file_system.apply_line(&line);
+    fn part_1(sizes: &Self::Input, _: &()) -> Answer {
+        sizes
+            .iter()
+            .filter(|size| **size < 100000)
+            .sum::<usize>()
+            .into()
+    }
+
+    fn part_2(sizes: &Self::Input, _: &()) -> Answer {
+        let total_used: usize = *sizes.last().unwrap();
+        let total_space = 70000000;
+        let free_space = total_space - total_used;
+        let required_space = 30000000;
+        let need_to_delete = required_space - free_space;
+        (*sizes
+            .iter()
+            .filter(|size| **size >= need_to_delete)
+            .min()
+            .unwrap())
+        .into()
+    }
+
+    /// A terminal session listing `dirs` directories and the files in them, which take up
+    /// between 45 and 65 million of the 70 million the disk has.
+    fn generate(rng: &mut Rng, dirs: usize) -> Option<String> {
+        /// A name made of letters, unique across the whole tree.
+        fn name(i: usize) -> String {
+            let mut name = String::new();
+            let mut i = i + 1;
+            while i > 0 {
+                name.push(char::from(b'a' + ((i - 1) % 26) as u8));
+                i = (i - 1) / 26;
+            }
+            name
+        }
+        fn list(dir: usize, children: &[Vec<usize>], files: &[Vec<usize>], res: &mut String) {
+            res.push_str("$ ls\n");
+            for child in &children[dir] {
+                res.push_str(&format!("dir {}\n", name(*child)));
+            }
+            for (i, size) in files[dir].iter().enumerate() {
+                res.push_str(&format!("{} {}{}.txt\n", size, name(dir), i));
+            }
+            for child in &children[dir] {
+                res.push_str(&format!("$ cd {}\n", name(*child)));
+                list(*child, children, files, res);
+                res.push_str("$ cd ..\n");
+            }
+        }
+
+        let dirs = dirs.max(1);
+        let mut children = vec![Vec::new(); dirs];
+        for dir in 1..dirs {
+            children[rng.index(dir)].push(dir);
+        }
+        // Weights first, scaled to the total below. The root has at least one file.
+        let mut files: Vec<Vec<usize>> = (0..dirs)
+            .map(|dir| {
+                (0..rng.range(i64::from(dir == 0)..4))
+                    .map(|_| rng.range(1..1000) as usize)
+                    .collect()
+            })
+            .collect();
+        let total = rng.range(45_000_000..65_000_000) as usize;
+        let weights: usize = files.iter().flatten().sum();
+        for size in files.iter_mut().flatten() {
+            *size = (*size * total / weights).max(1);
+        }
+        let mut res = String::from("$ cd /\n");
+        list(0, &children, &files, &mut res);
+        Some(res)
     }
-    //println!("File Sys: {:#?}", file_system.root);
-    let sizes = file_system.list_total_sizes();
-    let total_used: usize = *sizes.last().unwrap();
-    let total_space = 70000000;
-    let free_space = total_space - total_used;
-    let required_space = 30000000;
-    let need_to_delete = required_space - free_space;
-    *sizes
-        .iter()
-        .filter(|size| **size >= need_to_delete)
-        .min()
-        .unwrap()
 }
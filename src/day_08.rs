@@ -1,97 +1,111 @@
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::grid::{Grid, Pos};
+use crate::util::rng::Rng;
 
 #[derive(Debug, PartialEq)]
-struct Trees {
-    trees: Vec<Vec<i64>>,
-    rows: usize,
-    cols: usize,
+pub struct Trees {
+    trees: Grid<u32>,
 }
 
 impl Trees {
-    fn parse<'a>(lines: impl Iterator<Item = &'a str>) -> Self {
-        let mut trees = Vec::new();
-        for line in lines {
-            let mut current_row = Vec::new();
-            for char in line.chars() {
-                current_row.push(char.to_string().parse::<i64>().unwrap())
-            }
-            trees.push(current_row)
-        }
-        let rows = trees.len();
-        let cols = trees[0].len();
-        Self { trees, rows, cols }
+    fn parse<S: AsRef<str>>(lines: &[S]) -> Result<Self> {
+        let trees = Grid::parse(lines, |char| {
+            char.to_digit(10)
+                .ok_or_else(|| Error::invalid("Invalid tree height", char))
+        })?;
+        Ok(Self { trees })
     }
 
-    fn is_visible(&self, row: usize, col: usize) -> bool {
-        let height = self.trees[row][col];
-        (0..row).all(|row| self.trees[row][col] < height)
-            || (row + 1..self.rows).all(|row| self.trees[row][col] < height)
-            || (0..col).all(|col| self.trees[row][col] < height)
-            || (col + 1..self.cols).all(|col| self.trees[row][col] < height)
+    fn is_visible(&self, (x, y): Pos) -> bool {
+        let height = self.trees[(x, y)];
+        let row = self.trees.row(y);
+        row[..x].iter().all(|tree| *tree < height)
+            || row[x + 1..].iter().all(|tree| *tree < height)
+            || self.trees.column(x).take(y).all(|tree| *tree < height)
+            || self.trees.column(x).skip(y + 1).all(|tree| *tree < height)
     }
 
-    fn scenic_dir(&self, height: i64, tree_iter: impl Iterator<Item = (usize, usize)>) -> usize {
+    fn scenic_dir<'a>(height: u32, trees: impl Iterator<Item = &'a u32>) -> usize {
         let mut res = 0;
-        for (row, col) in tree_iter {
+        for tree in trees {
             res += 1;
-            if self.trees[row][col] >= height {
+            if *tree >= height {
                 return res;
             }
         }
         res
     }
 
-    fn scenic_score(&self, row: usize, col: usize) -> usize {
-        let height = self.trees[row][col];
-        let up = self.scenic_dir(height, (0..row).rev().map(|row| (row, col)));
-        let down = self.scenic_dir(height, ((row + 1)..self.rows).map(|row| (row, col)));
-        let left = self.scenic_dir(height, (0..col).rev().map(|col| (row, col)));
-        let right = self.scenic_dir(height, ((col + 1)..self.cols).map(|col| (row, col)));
+    fn scenic_score(&self, (x, y): Pos) -> usize {
+        let height = self.trees[(x, y)];
+        let row = self.trees.row(y);
+        let column = || self.trees.column(x);
+        let up = Self::scenic_dir(height, column().take(y).rev());
+        let down = Self::scenic_dir(height, column().skip(y + 1));
+        let left = Self::scenic_dir(height, row[..x].iter().rev());
+        let right = Self::scenic_dir(height, row[x + 1..].iter());
         up * down * left * right
     }
 }
 
-pub fn part_1(file: &str) -> usize {
-    let contents = util::read_str(file);
-    let lines = contents.lines();
-    let trees = Trees::parse(lines);
-    (0..trees.rows)
-        .map(|row| {
-            (0..trees.cols)
-                .filter(|col| trees.is_visible(row, *col))
-                .count()
-        })
-        .sum()
-}
+pub struct Day08;
+
+impl Solution for Day08 {
+    type Input = Trees;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Trees::parse(&util::read_lines(file)?)
+    }
 
-pub fn part_2(file: &str) -> usize {
-    let contents = util::read_str(file);
-    let lines = contents.lines();
-    let trees = Trees::parse(lines);
-    (0..trees.rows)
-        .map(|row| {
-            (0..trees.cols)
-                .map(|col| trees.scenic_score(row, col))
-                .max()
-                .unwrap()
-        })
-        .max()
-        .unwrap()
+    fn part_1(trees: &Self::Input, _: &()) -> Answer {
+        trees
+            .trees
+            .positions()
+            .filter(|pos| trees.is_visible(*pos))
+            .count()
+            .into()
+    }
+
+    fn part_2(trees: &Self::Input, _: &()) -> Answer {
+        trees
+            .trees
+            .positions()
+            .map(|pos| trees.scenic_score(pos))
+            .max()
+            .unwrap()
+            .into()
+    }
+
+    fn generate(rng: &mut Rng, side: usize) -> Option<String> {
+        Some(
+            (0..side.max(1))
+                .map(|_| {
+                    (0..side.max(1))
+                        .map(|_| char::from(b'0' + rng.index(10) as u8))
+                        .chain(['\n'])
+                        .collect::<String>()
+                })
+                .collect(),
+        )
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::Trees;
+    use crate::util::Grid;
 
     #[test]
     fn test_parse() {
-        let lines = vec!["1234", "5678", "9876"];
+        let lines = ["1234", "5678", "9876"];
         assert_eq!(
-            Trees::parse(lines.iter().copied()),
+            Trees::parse(&lines).unwrap(),
             Trees {
-                trees: vec!(vec!(1, 2, 3, 4), vec!(5, 6, 7, 8), vec!(9, 8, 7, 6)),
-                rows: 3,
-                cols: 4
+                trees: Grid::from_rows(vec!(vec!(1, 2, 3, 4), vec!(5, 6, 7, 8), vec!(9, 8, 7, 6)))
+                    .unwrap(),
             }
         );
     }
@@ -1,66 +1,19 @@
-use std::{collections::HashSet, ops};
+use std::collections::HashSet;
 
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::geom::Vec2;
+use crate::util::rng::Rng;
 
-#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
-struct Point {
-    x: i32,
-    y: i32,
-}
-
-impl ops::Add for Point {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        Point {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-        }
-    }
-}
+type Point = Vec2;
 
-impl ops::Sub for Point {
-    type Output = Self;
-    fn sub(self, rhs: Self) -> Self::Output {
-        Point {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        }
-    }
-}
-
-impl Point {
-    fn origin() -> Self {
-        Self { x: 0, y: 0 }
-    }
-
-    fn new(x: i32, y: i32) -> Self {
-        Self { x: x, y: y }
-    }
-
-    fn signum(self) -> Self {
-        Self {
-            x: self.x.signum(),
-            y: self.y.signum(),
-        }
-    }
-
-    fn abs(self) -> Self {
-        Self {
-            x: self.x.abs(),
-            y: self.y.abs(),
-        }
-    }
-
-    fn step_towards(self, other: Self) -> Self {
-        let diff = other - self;
-        let delta = diff.signum();
-        let abs = diff.abs();
-
-        if abs.x == 2 || abs.y == 2 {
-            self + delta
-        } else {
-            self
-        }
+/// Follows a knot that may be up to two steps away in any direction.
+fn step_towards(knot: Point, other: Point) -> Point {
+    if knot.chebyshev(other) > 1 {
+        knot + (other - knot).signum()
+    } else {
+        knot
     }
 }
 
@@ -73,62 +26,83 @@ impl Rope {
     fn new(knot_count: usize) -> Self {
         let mut tail_set = HashSet::new();
         let mut knots = Vec::new();
-        tail_set.insert(Point::origin());
+        tail_set.insert(Point::zero());
         for _ in 0..knot_count {
-            knots.push(Point::origin());
+            knots.push(Point::zero());
         }
         Self { knots, tail_set }
     }
 
     fn move_head(&mut self, delta: Point) {
-        self.knots[0] = self.knots[0] + delta;
+        self.knots[0] += delta;
         for i in 1..self.knots.len() {
-            self.knots[i] = self.knots[i].step_towards(self.knots[i - 1]);
+            self.knots[i] = step_towards(self.knots[i], self.knots[i - 1]);
         }
-        self.tail_set.insert(self.knots.last().unwrap().clone());
+        self.tail_set.insert(*self.knots.last().unwrap());
     }
 }
 
-struct Command {
+pub struct Command {
     delta: Point,
     steps: u32,
 }
 
 impl Command {
-    fn parse(line: &str) -> Self {
-        let mut tokens = line.split_whitespace();
-        let delta_str = tokens.next().unwrap();
-        let steps_str = tokens.next().unwrap();
+    fn parse(line: &str) -> Result<Self> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let delta_str = util::token(&tokens, 0)?;
+        let steps_str = util::token(&tokens, 1)?;
         let delta = match delta_str {
             "R" => Point::new(1, 0),
             "L" => Point::new(-1, 0),
             "U" => Point::new(0, 1),
             "D" => Point::new(0, -1),
-            _ => panic!("Illegal direction"),
+            _ => return Err(Error::invalid("Illegal direction", delta_str)),
         };
-        let steps = steps_str.parse().unwrap();
-        Self { delta, steps }
+        let steps = util::parse_token(steps_str)?;
+        Ok(Self { delta, steps })
     }
 }
 
-pub fn part_1(file: &str) -> usize {
-    let mut rope = Rope::new(2);
-    for line in util::read_lines(file) {
-        let Command { delta, steps } = Command::parse(&line);
-        for _ in 0..steps {
-            rope.move_head(delta);
+fn tail_positions(commands: &[Command], knot_count: usize) -> usize {
+    let mut rope = Rope::new(knot_count);
+    for Command { delta, steps } in commands {
+        for _ in 0..*steps {
+            rope.move_head(*delta);
         }
     }
     rope.tail_set.len()
 }
 
-pub fn part_2(file: &str) -> usize {
-    let mut rope = Rope::new(10);
-    for line in util::read_lines(file) {
-        let Command { delta, steps } = Command::parse(&line);
-        for _ in 0..steps {
-            rope.move_head(delta);
-        }
+pub struct Day09;
+
+impl Solution for Day09 {
+    type Input = Vec<Command>;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        util::lines(file)?.parse(Command::parse)
+    }
+
+    fn part_1(commands: &Self::Input, _: &()) -> Answer {
+        tail_positions(commands, 2).into()
+    }
+
+    fn part_2(commands: &Self::Input, _: &()) -> Answer {
+        tail_positions(commands, 10).into()
+    }
+
+    fn generate(rng: &mut Rng, commands: usize) -> Option<String> {
+        Some(
+            (0..commands)
+                .map(|_| {
+                    format!(
+                        "{} {}\n",
+                        rng.choose(&['U', 'D', 'L', 'R']),
+                        rng.range(1..20)
+                    )
+                })
+                .collect(),
+        )
     }
-    rope.tail_set.len()
 }
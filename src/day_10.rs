@@ -1,7 +1,10 @@
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
 
 #[derive(Copy, Clone, Debug)]
-struct DeviceState {
+pub struct DeviceState {
     cycle: i64,
     x: i64,
 }
@@ -25,57 +28,88 @@ impl Device {
         self.state.cycle += 1;
     }
 
-    fn apply_command(&mut self, line: &str) {
-        let mut iter = line.split_whitespace();
-        let command = iter.next().unwrap();
+    fn apply_command(&mut self, line: &str) -> Result<()> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let command = util::token(&tokens, 0)?;
         match command {
             "noop" => self.next_cycle(),
             "addx" => {
-                let y: i64 = iter.next().unwrap().parse().unwrap();
+                let y: i64 = util::parse_token(util::token(&tokens, 1)?)?;
                 self.next_cycle();
                 self.next_cycle();
                 self.state.x += y;
             }
-            _ => panic!("Invalid command"),
+            _ => return Err(Error::invalid("Invalid command", command)),
         }
+        Ok(())
     }
 
-    fn apply_all<'a>(&mut self, lines: impl Iterator<Item = &'a str>) {
-        for line in lines {
-            self.apply_command(line);
+    fn apply_all<'a>(&mut self, lines: impl Iterator<Item = &'a str>) -> Result<()> {
+        for (i, line) in lines.enumerate() {
+            self.apply_command(line)
+                .map_err(|err| err.locate_in(line).at_line(i + 1))?;
         }
         self.next_cycle();
+        Ok(())
     }
 }
 
-pub fn part_1(file: &str) -> i64 {
-    let mut device = Device::new();
-    device.apply_all(util::read_lines(file).iter().map(|s| s.as_str()));
-    device
-        .history
-        .iter()
-        .filter(|state| (state.cycle + 20) % 40 == 0)
-        .map(|state| state.cycle * state.x)
-        .sum()
-}
+pub struct Day10;
+
+impl Solution for Day10 {
+    /// The state of the device during every cycle.
+    type Input = Vec<DeviceState>;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        let mut device = Device::new();
+        device.apply_all(util::read_lines(file)?.iter().map(|s| s.as_str()))?;
+        Ok(device.history)
+    }
 
-pub fn part_2(file: &str) -> String {
-    let mut device = Device::new();
-    device.apply_all(util::read_lines(file).iter().map(|s| s.as_str()));
-    let mut out = String::new();
-    for state in device.history {
-        let cycle_pos = (state.cycle - 1) % 40;
-        if cycle_pos == 0 {
-            out.push_str("\n");
+    fn part_1(history: &Self::Input, _: &()) -> Answer {
+        history
+            .iter()
+            .filter(|state| (state.cycle + 20) % 40 == 0)
+            .map(|state| state.cycle * state.x)
+            .sum::<i64>()
+            .into()
+    }
+
+    fn part_2(history: &Self::Input, _: &()) -> Answer {
+        let mut out = String::new();
+        for state in history {
+            let cycle_pos = (state.cycle - 1) % 40;
+            if cycle_pos == 0 {
+                out.push('\n');
+            }
+            let pixel = if (cycle_pos - state.x).abs() <= 1 {
+                "#"
+            } else {
+                "."
+            };
+            out.push_str(pixel);
         }
-        let pixel = if (cycle_pos - state.x).abs() <= 1 {
-            "#"
-        } else {
-            "."
-        };
-        out.push_str(pixel);
+        out.into()
+    }
+
+    /// `commands` commands that keep the sprite on the screen.
+    fn generate(rng: &mut Rng, commands: usize) -> Option<String> {
+        let mut x = 1;
+        Some(
+            (0..commands)
+                .map(|_| {
+                    if rng.chance(0.3) {
+                        return "noop\n".to_string();
+                    }
+                    let next = rng.range(0..40);
+                    let y = next - x;
+                    x = next;
+                    format!("addx {}\n", y)
+                })
+                .collect(),
+        )
     }
-    out
 }
 
 #[cfg(test)]
@@ -86,8 +120,8 @@ mod test {
     #[test]
     fn test_device() {
         let mut device = Device::new();
-        let lines = vec!["noop", "addx 3", "addx -5"];
-        device.apply_all(lines.iter().copied());
+        let lines = ["noop", "addx 3", "addx -5"];
+        device.apply_all(lines.iter().copied()).unwrap();
         for state in &device.history {
             println!("{:?}", state);
         }
@@ -1,31 +1,35 @@
 use std::collections::VecDeque;
 
-use crate::util;
+use crate::error::{Error, Result};
+use crate::log::{debug, trace};
+use crate::solution::{Answer, Solution};
+use crate::util::rng::Rng;
+use crate::util::{self, Block};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum Operator {
     Plus,
     Times,
 }
 
 impl Operator {
-    fn apply(&self, left: usize, right: usize) -> usize {
+    fn apply(&self, left: usize, right: usize) -> Option<usize> {
         match self {
-            Self::Plus => left + right,
-            Self::Times => left * right,
+            Self::Plus => left.checked_add(right),
+            Self::Times => left.checked_mul(right),
         }
     }
 
-    fn parse(s: &str) -> Self {
+    fn parse(s: &str) -> Result<Self> {
         match s {
-            "+" => Self::Plus,
-            "*" => Self::Times,
-            _ => panic!("Illegal operator: {}", s),
+            "+" => Ok(Self::Plus),
+            "*" => Ok(Self::Times),
+            _ => Err(Error::invalid("Illegal operator", s)),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum Operand {
     Old,
     Number(usize),
@@ -39,16 +43,16 @@ impl Operand {
         }
     }
 
-    fn parse(s: &str) -> Self {
+    fn parse(s: &str) -> Result<Self> {
         if s == "old" {
-            Operand::Old
+            Ok(Operand::Old)
         } else {
-            Operand::Number(s.parse().expect("Operand is not a number!"))
+            Ok(Operand::Number(util::parse_token(s)?))
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Operation {
     left: Operand,
     operator: Operator,
@@ -56,13 +60,14 @@ struct Operation {
 }
 
 impl Operation {
-    fn apply(&self, old: usize) -> usize {
+    /// The new worry level, or `None` if it doesn't fit into a `usize`.
+    fn apply(&self, old: usize) -> Option<usize> {
         self.operator
             .apply(self.left.value(old), self.right.value(old))
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Monkey {
     items: VecDeque<usize>,
     operation: Operation,
@@ -73,66 +78,132 @@ struct Monkey {
 }
 
 impl Monkey {
-    fn parse<'a>(lines: &Vec<String>) -> Self {
-        let mut monkey = Self {
-            items: VecDeque::new(),
-            operation: Operation {
-                left: Operand::Old,
-                operator: Operator::Plus,
-                right: Operand::Old,
-            },
-            test_divisor: 0,
-            test_true_monkey: 0,
-            test_false_monkey: 0,
+    fn parse(lines: &[String]) -> Result<Self> {
+        let mut monkey = MonkeyLines::default();
+        util::parse_lines(lines, |line| monkey.parse_line(line))?;
+        let missing_target = || Error::new("Missing target");
+        Ok(Self {
+            items: monkey.items,
+            operation: monkey
+                .operation
+                .ok_or_else(|| Error::new("Missing operation"))?,
+            test_divisor: monkey
+                .test_divisor
+                .ok_or_else(|| Error::new("Missing test divisor"))?,
+            test_true_monkey: monkey.test_true_monkey.ok_or_else(missing_target)?,
+            test_false_monkey: monkey.test_false_monkey.ok_or_else(missing_target)?,
             activeness: 0,
-        };
-        for line in lines {
-            let line = line.replace(",", "");
-            let tokens: Vec<&str> = line.split_whitespace().collect();
-
-            match tokens[0] {
-                "Monkey" => (),
-                "Starting" => {
-                    for item in tokens[2..].iter() {
-                        monkey.items.push_back(item.parse().unwrap());
-                    }
-                }
-                "Operation:" => {
-                    monkey.operation.left = Operand::parse(tokens[3]);
-                    monkey.operation.operator = Operator::parse(tokens[4]);
-                    monkey.operation.right = Operand::parse(tokens[5])
+        })
+    }
+}
+
+/// The attributes of a monkey read so far, any of which its block may lack.
+#[derive(Default)]
+struct MonkeyLines {
+    items: VecDeque<usize>,
+    operation: Option<Operation>,
+    test_divisor: Option<usize>,
+    test_true_monkey: Option<usize>,
+    test_false_monkey: Option<usize>,
+}
+
+impl MonkeyLines {
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let line = line.replace(',', "");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match util::token(&tokens, 0)? {
+            "Monkey" => (),
+            "Starting" => {
+                for item in tokens.iter().skip(2) {
+                    self.items.push_back(util::parse_token(item)?);
                 }
-                "Test:" => monkey.test_divisor = tokens[3].parse().unwrap(),
-                "If" if tokens[1] == "true:" => {
-                    monkey.test_true_monkey = tokens[5].parse().unwrap()
+            }
+            "Operation:" => {
+                self.operation = Some(Operation {
+                    left: Operand::parse(util::token(&tokens, 3)?)?,
+                    operator: Operator::parse(util::token(&tokens, 4)?)?,
+                    right: Operand::parse(util::token(&tokens, 5)?)?,
+                })
+            }
+            "Test:" => {
+                let token = util::token(&tokens, 3)?;
+                let divisor = util::parse_token(token)?;
+                if divisor == 0 {
+                    return Err(Error::invalid("Can't test divisibility by zero", token));
                 }
-                "If" if tokens[1] == "false:" => {
-                    monkey.test_false_monkey = tokens[5].parse().unwrap()
+                self.test_divisor = Some(divisor);
+            }
+            "If" => {
+                let target = Some(util::parse_token(util::token(&tokens, 5)?)?);
+                match util::token(&tokens, 1)? {
+                    "true:" => self.test_true_monkey = target,
+                    "false:" => self.test_false_monkey = target,
+                    condition => return Err(Error::invalid("Can't parse condition", condition)),
                 }
-                _ => panic!("Can't parse {}", tokens[0]),
             }
+            attribute => return Err(Error::invalid("Can't parse", attribute)),
         }
-        monkey
+        Ok(())
     }
 }
 
-#[derive(Debug)]
-struct Monkeys {
+/// The rounds of part 1, where worry levels are divided by three after each inspection.
+const RELIEF_ROUNDS: usize = 20;
+
+#[derive(Clone, Debug)]
+pub struct Monkeys {
     monkeys: Vec<Monkey>,
     mod_divisor: usize,
 }
 
 impl Monkeys {
-    fn parse(blocks: Vec<Vec<String>>) -> Self {
-        let monkeys: Vec<Monkey> = blocks.iter().map(Monkey::parse).collect();
-        let mod_divisor = monkeys.iter().map(|monkey| monkey.test_divisor).product();
-        Self {
+    fn parse(blocks: &[Block]) -> Result<Self> {
+        let monkeys: Vec<Monkey> = util::parse_blocks(blocks, Monkey::parse)?;
+        if monkeys.len() < 2 {
+            return Err(Error::new("Need at least two monkeys"));
+        }
+        for (monkey, block) in monkeys.iter().zip(blocks) {
+            for target in [monkey.test_true_monkey, monkey.test_false_monkey] {
+                if target >= monkeys.len() {
+                    return Err(Error::invalid("Unknown monkey", target.to_string())
+                        .at_line(block.start + 1));
+                }
+            }
+        }
+        let mod_divisor = monkeys
+            .iter()
+            .try_fold(1usize, |product, monkey| {
+                product.checked_mul(monkey.test_divisor)
+            })
+            .ok_or_else(|| Error::new("Test divisors too large"))?;
+        // Without relief worry levels stay below the product of the divisors, or at the
+        // largest starting item. Operations only grow with the old level, so if they don't
+        // overflow for the largest one, they never do.
+        let largest = monkeys
+            .iter()
+            .flat_map(|monkey| monkey.items.iter().copied())
+            .fold(mod_divisor - 1, usize::max);
+        if let Some((_, block)) = monkeys
+            .iter()
+            .zip(blocks)
+            .find(|(monkey, _)| monkey.operation.apply(largest).is_none())
+        {
+            return Err(Error::new("Worry levels could overflow").at_line(block.start + 1));
+        }
+        let monkeys = Self {
             monkeys,
             mod_divisor,
+        };
+        // With relief they aren't bounded, but the few rounds of part 1 are quick to try.
+        if monkeys.clone().run_rounds(RELIEF_ROUNDS, true).is_none() {
+            return Err(Error::new("Worry levels overflow with relief"));
         }
+        Ok(monkeys)
     }
 
-    fn make_turn(&mut self, monkey_id: usize, do_relief: bool) {
+    /// Returns `None` if a worry level overflows.
+    fn make_turn(&mut self, monkey_id: usize, do_relief: bool) -> Option<()> {
         while let Some(item) = self.monkeys[monkey_id].items.pop_front() {
             let monkey = &mut self.monkeys[monkey_id];
             monkey.activeness += 1;
@@ -144,35 +215,43 @@ impl Monkeys {
                 items: _,
                 activeness: _,
             } = monkey;
-            // println!("inspecting {}", item);
-            let item = operation.apply(item);
-            // println!("worry level is now {}", item);
+            trace!(
+                "Monkey {} inspects an item with worry level {}",
+                monkey_id,
+                item
+            );
+            let item = operation.apply(item)?;
+            trace!("Worry level is now {}", item);
             let item = if do_relief {
                 item / 3
             } else {
                 item % self.mod_divisor
             };
-            // println!("Gets divided to {}", item);
+            trace!("Worry level is reduced to {}", item);
             let next_monkey = if item % *test_divisor == 0 {
                 *test_true_monkey
             } else {
                 *test_false_monkey
             };
-            // println!("Next monkey is {}", next_monkey);
+            trace!("Item is thrown to monkey {}", next_monkey);
             self.monkeys[next_monkey].items.push_back(item);
-            // panic!("Stop");
         }
+        Some(())
     }
 
-    fn run_round(&mut self, do_relief: bool) {
-        for monkey_id in 0..self.monkeys.len() {
-            self.make_turn(monkey_id, do_relief);
+    /// Returns `None` if a worry level overflows.
+    fn run_rounds(&mut self, rounds: usize, do_relief: bool) -> Option<()> {
+        for _ in 0..rounds {
+            for monkey_id in 0..self.monkeys.len() {
+                self.make_turn(monkey_id, do_relief)?;
+            }
         }
+        Some(())
     }
 
-    fn print_items(&self) {
+    fn log_items(&self) {
         for (id, monkey) in self.monkeys.iter().enumerate() {
-            println!(
+            debug!(
                 "Monkey {}: {}",
                 id,
                 monkey
@@ -201,22 +280,117 @@ impl Monkeys {
     }
 }
 
-pub fn part_1(file: &str) -> usize {
-    let mut monkeys = Monkeys::parse(util::read_blocks(file));
-    for _ in 0..20 {
-        monkeys.run_round(true);
+pub struct Day11;
+
+impl Solution for Day11 {
+    type Input = Monkeys;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Monkeys::parse(&util::read_blocks(file)?)
+    }
+
+    fn part_1(monkeys: &Self::Input, _: &()) -> Answer {
+        let mut monkeys = monkeys.clone();
+        monkeys
+            .run_rounds(RELIEF_ROUNDS, true)
+            .expect("Worry levels were checked by the parser");
+        monkeys.log_items();
+        monkeys.monkey_business().into()
+    }
+
+    fn part_2(monkeys: &Self::Input, _: &()) -> Answer {
+        let mut monkeys = monkeys.clone();
+        monkeys
+            .run_rounds(10000, false)
+            .expect("Worry levels were bounded by the parser");
+        monkeys.log_items();
+        monkeys.monkey_business().into()
     }
 
-    // monkeys.print_items();
-    monkeys.monkey_business()
+    /// `items` items spread between up to eight monkeys. The divisors are distinct primes,
+    /// so their product stays small. Monkeys multiply by at most 3, which the relief of
+    /// part 1 undoes, so worry levels can't overflow there.
+    fn generate(rng: &mut Rng, items: usize) -> Option<String> {
+        let mut divisors = [2, 3, 5, 7, 11, 13, 17, 19];
+        rng.shuffle(&mut divisors);
+        let count = items.clamp(2, divisors.len());
+        let mut monkey_items = vec![Vec::new(); count];
+        for _ in 0..items {
+            monkey_items[rng.index(count)].push(rng.range(50..100).to_string());
+        }
+        let monkeys: Vec<String> = (0..count)
+            .map(|monkey| {
+                let operation = if rng.chance(0.5) {
+                    format!("* {}", rng.range(2..4))
+                } else {
+                    format!("+ {}", rng.range(1..9))
+                };
+                // Two different monkeys, neither of them this one.
+                let other = |rng: &mut Rng, skip: usize| (skip + 1 + rng.index(count - 1)) % count;
+                let if_true = other(rng, monkey);
+                let mut if_false = other(rng, monkey);
+                if count > 2 {
+                    while if_false == if_true {
+                        if_false = other(rng, monkey);
+                    }
+                }
+                format!(
+                    "Monkey {}:\n  Starting items: {}\n  Operation: new = old {}\n  \
+                     Test: divisible by {}\n    If true: throw to monkey {}\n    \
+                     If false: throw to monkey {}\n",
+                    monkey,
+                    monkey_items[monkey].join(", "),
+                    operation,
+                    divisors[monkey],
+                    if_true,
+                    if_false
+                )
+            })
+            .collect();
+        Some(monkeys.join("\n"))
+    }
 }
 
-pub fn part_2(file: &str) -> usize {
-    let mut monkeys = Monkeys::parse(util::read_blocks(file));
-    for _ in 0..10000 {
-        monkeys.run_round(false);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Monkeys> {
+        Monkeys::parse(&util::lines_to_blocks(
+            input.lines().map(str::to_string).collect(),
+        ))
     }
 
-    // monkeys.print_items();
-    monkeys.monkey_business()
+    const OTHER: &str = "Monkey 1:\n  Starting items: 1\n  Operation: new = old + 1\n  \
+                         Test: divisible by 2\n    If true: throw to monkey 0\n    \
+                         If false: throw to monkey 0\n";
+
+    #[test]
+    fn test_missing_lines() {
+        let monkey = "Monkey 0:\n  Starting items: 79\n  Test: divisible by 3\n    \
+                      If true: throw to monkey 1\n";
+        let err = parse(&format!("{}\n{}", monkey, OTHER)).unwrap_err();
+        assert_eq!(err.message, "Missing operation");
+        let monkey = monkey.replace("  Test", "  Operation: new = old * 2\n  Test");
+        let err = parse(&format!("{}\n{}", monkey, OTHER)).unwrap_err();
+        assert_eq!(err.message, "Missing target");
+        let monkey = monkey + "    If false: throw to monkey 1\n";
+        assert!(parse(&format!("{}\n{}", monkey, OTHER)).is_ok());
+    }
+
+    #[test]
+    fn test_worry_overflow() {
+        let monkey = "Monkey 0:\n  Starting items: 4294967296\n  Operation: new = old * old\n  \
+                      Test: divisible by 3\n    If true: throw to monkey 1\n    \
+                      If false: throw to monkey 1\n";
+        let err = parse(&format!("{}\n{}", monkey, OTHER)).unwrap_err();
+        assert_eq!(err.message, "Worry levels could overflow");
+        assert_eq!(err.line, Some(1));
+        let monkey = monkey.replace("4294967296", "4294967295");
+        let err = parse(&format!("{}\n{}", monkey, OTHER)).unwrap_err();
+        assert_eq!(err.message, "Worry levels overflow with relief");
+        let monkey = monkey.replace("old * old", "old * 19");
+        assert!(parse(&format!("{}\n{}", monkey, OTHER)).is_ok());
+    }
 }
@@ -1,129 +1,118 @@
-use std::collections::{HashMap, HashSet};
-
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
-
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Point(usize, usize);
+use crate::util::grid::{Grid, Pos};
+use crate::util::rng::Rng;
+use crate::util::search;
 
 #[derive(Debug)]
-struct HeightMap {
-    field: Vec<Vec<u8>>,
-    start: Point,
-    end: Point,
-    width: usize,
-    height: usize,
+pub struct HeightMap {
+    field: Grid<u8>,
+    start: Pos,
+    end: Pos,
 }
 
 impl HeightMap {
-    fn parse(lines: &[String]) -> Self {
-        let map_height = lines.len();
-        let map_width = lines[0].bytes().len();
-        let mut start = Point(0, 0);
-        let mut end = Point(0, 1);
-        let mut field: Vec<Vec<u8>> = Vec::new();
-        for (y, line) in lines.iter().enumerate() {
-            let mut row: Vec<u8> = Vec::new();
-            for (x, char) in line.bytes().enumerate() {
-                let height = match char {
-                    b'S' => {
-                        start = Point(x, y);
-                        b'a'
-                    }
-                    b'E' => {
-                        end = Point(x, y);
-                        b'z'
-                    }
-                    _ => char,
-                };
-                row.push(height);
-            }
-            field.push(row);
-        }
-        Self {
-            field,
-            start,
-            end,
-            width: map_width,
-            height: map_height,
-        }
+    fn parse(lines: &[String]) -> Result<Self> {
+        let chars = Grid::parse(lines, |char| match char {
+            'S' | 'E' | 'a'..='z' => Ok(char),
+            _ => Err(Error::invalid("Invalid height", char)),
+        })?;
+        let find = |target: char| {
+            chars
+                .iter()
+                .find(|(_, char)| **char == target)
+                .map(|(pos, _)| pos)
+        };
+        let start = find('S').ok_or_else(|| Error::new("Missing start S"))?;
+        let end = find('E').ok_or_else(|| Error::new("Missing end E"))?;
+        let field = chars.map(|char| match char {
+            'S' => b'a',
+            'E' => b'z',
+            _ => *char as u8,
+        });
+        Ok(Self { field, start, end })
     }
 
-    fn point_height(&self, p: &Point) -> u8 {
-        self.field[p.1][p.0]
+    /// The fewest steps from any of the starts to the end, climbing at most one step up.
+    fn shortest_path(&self, start: &[Pos]) -> usize {
+        let neighbours = |pos: &Pos| {
+            let max_height = self.field[*pos] + 1;
+            self.field
+                .neighbours_4(*pos)
+                .filter(move |neighbour| self.field[*neighbour] <= max_height)
+        };
+        search::bfs(start.iter().copied(), neighbours, |pos| *pos == self.end)
+            .expect("No path to the end")
+            .cost
     }
 
-    fn neighbours(&self, Point(x, y): &Point) -> Vec<Point> {
-        let mut res = Vec::new();
-        if *x > 0usize {
-            res.push(Point(x - 1, *y));
-        }
-        if *y > 0usize {
-            res.push(Point(*x, y - 1));
-        }
-        if x + 1 < self.width {
-            res.push(Point(x + 1, *y));
-        }
-        if y + 1 < self.height {
-            res.push(Point(*x, y + 1));
-        }
-        res
+    fn lowest_points(&self) -> Vec<Pos> {
+        self.field
+            .iter()
+            .filter(|(_, height)| **height == b'a')
+            .map(|(pos, _)| pos)
+            .collect()
     }
+}
 
-    fn shortest_path(&self, start: &[Point]) -> usize {
-        let mut steps = 0;
-        let mut steps_to: HashMap<Point, usize> = HashMap::new();
-        let mut search_now: HashSet<Point> = HashSet::new();
-        for start_point in start {
-            steps_to.insert(*start_point, 0);
-            search_now.insert(*start_point);
-        }
+pub struct Day12;
 
-        loop {
-            // println!("{:?}", steps_to);
-            steps += 1;
-            let mut search_next = HashSet::new();
-            for point in &search_now {
-                let max_neighb_height = self.point_height(point) + 1;
-                for neighbor in self.neighbours(point) {
-                    if self.point_height(&neighbor) <= max_neighb_height {
-                        if neighbor == self.end {
-                            return steps;
-                        } else {
-                            match steps_to.get(&neighbor) {
-                                Some(_) => (),
-                                None => {
-                                    steps_to.insert(neighbor, steps);
-                                    search_next.insert(neighbor);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            search_now = search_next;
-        }
+impl Solution for Day12 {
+    type Input = HeightMap;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        HeightMap::parse(&util::read_lines(file)?)
     }
 
-    fn lowest_points(&self) -> Vec<Point> {
-        let mut v = Vec::new();
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let p = Point(x, y);
-                if self.point_height(&p) == b'a' {
-                    v.push(p);
-                }
-            }
-        }
-        v
+    fn part_1(map: &Self::Input, _: &()) -> Answer {
+        map.shortest_path(&[map.start]).into()
     }
-}
 
-pub fn part_1(file: &str) -> usize {
-    let map = HeightMap::parse(&util::read_lines(file));
-    map.shortest_path(&vec![map.start])
-}
+    fn part_2(map: &Self::Input, _: &()) -> Answer {
+        map.shortest_path(&map.lowest_points()).into()
+    }
 
-pub fn part_2(file: &str) -> usize {
-    let map = HeightMap::parse(&util::read_lines(file));
-    map.shortest_path(&map.lowest_points())
+    /// A square map with heights rising from the left to the right and a path from the start
+    /// in the first column to the end in the last one that climbs at most one step at a time.
+    /// The side is at least 26 tiles, so that the path is long enough to climb from a to z.
+    fn generate(rng: &mut Rng, side: usize) -> Option<String> {
+        let side = side.max(26);
+        let rows = (0..side)
+            .map(|_| {
+                (0..side)
+                    .map(|x| {
+                        let base = (25 * x / (side - 1)) as i64;
+                        b'a' + (base + rng.range(-3..4)).clamp(0, 25) as u8
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut heights = Grid::from_rows(rows).expect("Rows of the same width");
+        let start = (0, rng.index(side));
+        let end = (side - 1, rng.index(side));
+        let mut moves = vec![(1, 0); side - 1];
+        let vertical = if end.1 > start.1 { (0, 1) } else { (0, -1) };
+        moves.extend(std::iter::repeat_n(vertical, end.1.abs_diff(start.1)));
+        rng.shuffle(&mut moves);
+        let mut pos = start;
+        for (i, (dx, dy)) in moves.iter().enumerate() {
+            pos = (pos.0 + dx, pos.1.wrapping_add_signed(*dy));
+            heights[pos] = b'a' + (25 * (i + 1) / moves.len()) as u8;
+        }
+        heights[start] = b'S';
+        heights[end] = b'E';
+        Some(
+            heights
+                .rows()
+                .map(|row| {
+                    row.iter()
+                        .map(|height| char::from(*height))
+                        .chain(['\n'])
+                        .collect::<String>()
+                })
+                .collect(),
+        )
+    }
 }
@@ -1,9 +1,14 @@
 use std::str::Chars;
 
+use crate::error::{Error, Result};
+use crate::log::trace;
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
 
+/// A packet: a number or a list of packets.
 #[derive(Debug)]
-enum Expr {
+pub enum Expr {
     Number(i64),
     List(Vec<Expr>),
 }
@@ -11,8 +16,13 @@ enum Expr {
 use Expr::*;
 
 impl Expr {
-    fn compare(&self, other: &Self) -> i32 {
-        // println!("Compare:\n  {:?}\n  {:?}", self, other);
+    pub fn parse(line: &str) -> Result<Self> {
+        Parser::parse(line)
+    }
+
+    /// Returns -1, 0 or 1 if `self` is ordered before, like or after `other`.
+    pub fn compare(&self, other: &Self) -> i32 {
+        trace!("Compare {:?} with {:?}", self, other);
         match (self, other) {
             (Number(x), Number(y)) => {
                 if x < y {
@@ -26,7 +36,6 @@ impl Expr {
             (List(xs), List(ys)) => {
                 let mut i = 0;
                 loop {
-                    // println!("Loop {}", i);
                     let left = xs.get(i);
                     let right = ys.get(i);
                     match (left, right) {
@@ -57,9 +66,14 @@ impl Expr {
     }
 }
 
+/// How deep lists may be nested before the parser gives up rather than overflow the stack.
+const MAX_DEPTH: usize = 256;
+
 struct Parser<'a> {
     current_char: Option<char>,
     iterator: Chars<'a>,
+    position: usize,
+    depth: usize,
 }
 
 impl<'a> Parser<'a> {
@@ -69,72 +83,100 @@ impl<'a> Parser<'a> {
         Self {
             current_char: current,
             iterator,
+            position: 0,
+            depth: 0,
         }
     }
 
-    fn current(&self) -> char {
-        self.current_char.expect("Unexpected end of line")
+    fn error(&self, message: &str) -> Error {
+        let err = match self.current_char {
+            Some(c) => Error::invalid(message, c),
+            None => Error::new(message),
+        };
+        err.at_column(self.position + 1)
+    }
+
+    fn current(&self) -> Result<char> {
+        self.current_char
+            .ok_or_else(|| self.error("Unexpected end of line"))
     }
 
     fn next(&mut self) {
         self.current_char = self.iterator.next();
+        self.position += 1;
     }
 
-    fn parse_number(&mut self) -> i64 {
+    fn parse_number(&mut self) -> Result<i64> {
+        let start = self.position;
         let mut num_str = String::new();
-        while matches!(self.current(), '0'..='9') {
-            num_str.push(self.current());
+        while self.current()?.is_ascii_digit() {
+            num_str.push(self.current()?);
             self.next();
         }
-        num_str
-            .parse()
-            .expect(&format!("Failed to parse: {}", num_str))
+        if num_str.is_empty() {
+            return Err(self.error("Expected a number or list"));
+        }
+        num_str.parse().map_err(|_| {
+            Error::invalid("Number out of range", num_str.as_str()).at_column(start + 1)
+        })
     }
 
-    fn parse_expr(&mut self) -> Expr {
-        if self.current() == '[' {
-            Expr::List(self.parse_list())
+    fn parse_expr(&mut self) -> Result<Expr> {
+        if self.current()? == '[' {
+            Ok(Expr::List(self.parse_list()?))
         } else {
-            Expr::Number(self.parse_number())
+            Ok(Expr::Number(self.parse_number()?))
         }
     }
-    fn parse_list(&mut self) -> Vec<Expr> {
+
+    fn parse_list(&mut self) -> Result<Vec<Expr>> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("Lists nested too deeply"));
+        }
+        self.depth += 1;
         let mut res = Vec::new();
         self.next();
-        while self.current() != ']' {
-            res.push(self.parse_expr());
-            if self.current() == ',' {
-                self.next()
+        if self.current()? != ']' {
+            loop {
+                res.push(self.parse_expr()?);
+                match self.current()? {
+                    ',' => self.next(),
+                    ']' => break,
+                    _ => return Err(self.error("Expected , or ]")),
+                }
             }
         }
         self.next();
-        res
+        self.depth -= 1;
+        Ok(res)
     }
 
-    fn parse(line: &'a str) -> Expr {
-        // println!("Parse line: {}", line);
+    fn parse(line: &'a str) -> Result<Expr> {
         let mut parser = Self::new(line);
-        let res = parser.parse_expr();
-        assert!(
-            parser.iterator.next().is_none(),
-            "Expression ended before end of line"
-        );
-        res
+        let res = parser.parse_expr()?;
+        if parser.current_char.is_some() {
+            return Err(parser.error("Expression ended before end of line"));
+        }
+        Ok(res)
     }
 }
 
 #[derive(Debug)]
-struct PacketPair {
+pub struct PacketPair {
     left: Expr,
     right: Expr,
 }
 
 impl PacketPair {
-    fn parse(block: &Vec<String>) -> Self {
-        Self {
-            left: Parser::parse(&block[0]),
-            right: Parser::parse(&block[1]),
+    fn parse(block: &[String]) -> Result<Self> {
+        if block.len() != 2 {
+            return Err(Error::new("Expected a pair of packets").at_line(1));
         }
+        let mut packets = util::parse_lines(block, Expr::parse)?.into_iter();
+        Ok(Self {
+            left: packets.next().unwrap(),
+            right: packets.next().unwrap(),
+        })
     }
 
     fn is_in_order(&self) -> bool {
@@ -142,19 +184,6 @@ impl PacketPair {
     }
 }
 
-pub fn part_1(file: &str) -> usize {
-    let packet_pairs: Vec<PacketPair> = util::read_blocks(file)
-        .iter()
-        .map(PacketPair::parse)
-        .collect();
-    packet_pairs
-        .iter()
-        .enumerate()
-        .filter(|(_, pair)| pair.is_in_order())
-        .map(|(i, _)| i + 1)
-        .sum()
-}
-
 fn insert_sorted<'a>(v: &mut Vec<&'a Expr>, e: &'a Expr) {
     let search = v.iter().enumerate().find(|(_, item)| e.compare(item) == -1);
     match search {
@@ -163,33 +192,125 @@ fn insert_sorted<'a>(v: &mut Vec<&'a Expr>, e: &'a Expr) {
     }
 }
 
-pub fn part_2(file: &str) -> usize {
-    let packet_pairs: Vec<PacketPair> = util::read_blocks(file)
-        .iter()
-        .map(PacketPair::parse)
-        .collect();
-    let mut v: Vec<&Expr> = Vec::new();
-    let diviver_1 = Parser::parse("[[2]]");
-    let diviver_2 = Parser::parse("[[6]]");
-    {
-        insert_sorted(&mut v, &diviver_1);
-    }
-    {
-        insert_sorted(&mut v, &diviver_2);
-    }
-    for pair in &packet_pairs {
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Input = Vec<PacketPair>;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        util::parse_blocks(&util::read_blocks(file)?, PacketPair::parse)
+    }
+
+    fn part_1(packet_pairs: &Self::Input, _: &()) -> Answer {
+        packet_pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, pair)| pair.is_in_order())
+            .map(|(i, _)| i + 1)
+            .sum::<usize>()
+            .into()
+    }
+
+    fn part_2(packet_pairs: &Self::Input, _: &()) -> Answer {
+        let mut v: Vec<&Expr> = Vec::new();
+        let diviver_1 = Expr::parse("[[2]]").unwrap();
+        let diviver_2 = Expr::parse("[[6]]").unwrap();
         {
-            insert_sorted(&mut v, &pair.left);
+            insert_sorted(&mut v, &diviver_1);
         }
         {
-            insert_sorted(&mut v, &pair.right);
+            insert_sorted(&mut v, &diviver_2);
+        }
+        for pair in packet_pairs {
+            {
+                insert_sorted(&mut v, &pair.left);
+            }
+            {
+                insert_sorted(&mut v, &pair.right);
+            }
+        }
+        fn find_packet(v: &Vec<&Expr>, e: &Expr) -> Option<usize> {
+            let r = v.iter().enumerate().find(|(_, item)| e.compare(item) == 0);
+            r.map(|t| t.0 + 1)
+        }
+        let p1 = find_packet(&v, &diviver_1).expect("Didn't find divider 1");
+        let p2 = find_packet(&v, &diviver_2).expect("Didn't find divider 2");
+        (p1 * p2).into()
+    }
+
+    /// `pairs` pairs of packets with numbers up to 10 and lists nested up to four deep.
+    fn generate(rng: &mut Rng, pairs: usize) -> Option<String> {
+        fn packet(rng: &mut Rng, depth: usize) -> String {
+            if depth > 0 && (depth == 4 || rng.chance(0.6)) {
+                return rng.range(0..11).to_string();
+            }
+            let items: Vec<String> = (0..rng.range(0..5))
+                .map(|_| packet(rng, depth + 1))
+                .collect();
+            format!("[{}]", items.join(","))
+        }
+        let pairs: Vec<String> = (0..pairs)
+            .map(|_| format!("{}\n{}\n", packet(rng, 0), packet(rng, 0)))
+            .collect();
+        Some(pairs.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::rng::Rng;
+
+    /// Small numbers and short lists, so that equal prefixes and mixed types are common.
+    fn random_expr(rng: &mut Rng, depth: usize) -> Expr {
+        if depth == 0 || rng.chance(0.4) {
+            Number(rng.range(0..4))
+        } else {
+            List(
+                (0..rng.index(4))
+                    .map(|_| random_expr(rng, depth - 1))
+                    .collect(),
+            )
         }
     }
-    fn find_packet(v: &Vec<&Expr>, e: &Expr) -> Option<usize> {
-        let r = v.iter().enumerate().find(|(_, item)| e.compare(item) == 0);
-        r.map(|t| t.0 + 1)
+
+    #[test]
+    fn test_parse_deep_lists() {
+        let line = "[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH);
+        assert!(Expr::parse(&line).is_ok());
+        let line = "[".repeat(100000) + &"]".repeat(100000);
+        assert_eq!(
+            Expr::parse(&line).unwrap_err(),
+            Error::invalid("Lists nested too deeply", '[').at_column(MAX_DEPTH + 1)
+        );
+    }
+
+    #[test]
+    fn test_parse_trailing_comma() {
+        assert!(Expr::parse("[1,[]]").is_ok());
+        for (line, column) in [("[1,]", 4), ("[[],]", 5)] {
+            assert_eq!(
+                Expr::parse(line).unwrap_err(),
+                Error::invalid("Expected a number or list", ']').at_column(column)
+            );
+        }
+        assert!(Expr::parse("[,]").is_err());
+    }
+
+    #[test]
+    fn test_compare_is_total_order() {
+        let mut rng = Rng::new(13);
+        for _ in 0..2000 {
+            let [a, b, c] = [(); 3].map(|_| random_expr(&mut rng, 4));
+            assert_eq!(a.compare(&a), 0, "{:?}", a);
+            assert_eq!(a.compare(&b), -b.compare(&a), "{:?} and {:?}", a, b);
+            if a.compare(&b) <= 0 && b.compare(&c) <= 0 {
+                assert!(a.compare(&c) <= 0, "{:?}, {:?} and {:?}", a, b, c);
+            }
+            if a.compare(&b) == 0 {
+                assert_eq!(a.compare(&c), b.compare(&c), "{:?}, {:?} and {:?}", a, b, c);
+            }
+        }
     }
-    let p1 = find_packet(&v, &diviver_1).expect("Didn't find divider 1");
-    let p2 = find_packet(&v, &diviver_2).expect("Didn't find divider 2");
-    p1 * p2
 }
@@ -1,76 +1,46 @@
-use std::collections::HashSet;
+use crate::error::{Error, Result};
+use crate::log::trace;
+use crate::solution::{Answer, Solution};
+use crate::util;
+use crate::util::geom::Vec2;
+use crate::util::rng::Rng;
+use crate::util::Grid;
 
 type Num = i32;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Point {
-    x: Num,
-    y: Num,
-}
+type Point = Vec2<Num>;
 
-#[derive(Clone, Copy, Debug)]
-enum Dir {
-    Up,
-    Down,
-    Left,
-    Right,
-    DownLeft,
-    DownRight,
-}
-
-use Dir::*;
-
-use crate::util;
-
-impl Point {
-    fn step(self, dir: Dir) -> Self {
-        let mut x = self.x;
-        let mut y = self.y;
-        match dir {
-            Up => y -= 1,
-            Down => y += 1,
-            Left => x -= 1,
-            Right => x += 1,
-            DownLeft => {
-                x -= 1;
-                y += 1;
-            }
-            DownRight => {
-                x += 1;
-                y += 1;
-            }
-        }
-        Self { x, y }
-    }
-
-    fn dir_of(&self, other: &Self) -> Dir {
-        match ((other.x - self.x).signum(), (other.y - self.y).signum()) {
-            (0, -1) => Up,
-            (0, 1) => Down,
-            (-1, 0) => Left,
-            (1, 0) => Right,
-            _ => panic!("other point not vertical or horizontal from this one"),
-        }
-    }
-}
+const DOWN: Point = Vec2 { x: 0, y: 1 };
+const DOWN_LEFT: Point = Vec2 { x: -1, y: 1 };
+const DOWN_RIGHT: Point = Vec2 { x: 1, y: 1 };
 
 struct Path {
     points: Vec<Point>,
 }
 
 impl Path {
-    fn parse(line: &str) -> Self {
-        Self {
-            points: line
-                .split(" -> ")
-                .map(|point_str| {
-                    let mut iter = point_str.split(",");
-                    let x = iter.next().unwrap().parse().unwrap();
-                    let y = iter.next().unwrap().parse().unwrap();
-                    Point { x, y }
+    fn parse(line: &str) -> Result<Self> {
+        let points: Vec<Point> = line
+            .split(" -> ")
+            .map(|point_str| {
+                let (x, y) = point_str
+                    .split_once(',')
+                    .ok_or_else(|| Error::invalid("Expected a point like 498,4", point_str))?;
+                Ok(Point {
+                    x: util::parse_token(x)?,
+                    y: util::parse_token(y)?,
                 })
-                .collect(),
+            })
+            .collect::<Result<_>>()?;
+        for segment in points.windows(2) {
+            if (segment[0].x == segment[1].x) == (segment[0].y == segment[1].y) {
+                return Err(Error::invalid(
+                    "Segment is neither horizontal nor vertical",
+                    format!("{},{}", segment[1].x, segment[1].y),
+                ));
+            }
         }
+        Ok(Self { points })
     }
 
     fn points(&self) -> Vec<Point> {
@@ -78,12 +48,10 @@ impl Path {
         let mut points = self.points.iter();
         let mut current = *points.next().unwrap();
         ret.push(current);
-        while let Some(end) = points.next() {
-            let dir = current.dir_of(end);
+        for end in points {
+            let dir = (*end - current).signum();
             while current != *end {
-                // println!("cur {:?}, end: {:?}", current, end);
-                current = current.step(dir);
-                // println!("after step in {:?} => {:?}", dir, current);
+                current += dir;
                 ret.push(current);
             }
         }
@@ -91,58 +59,83 @@ impl Path {
     }
 }
 
-struct Grid {
-    fields: HashSet<Point>,
+#[derive(Clone)]
+pub struct Cave {
+    occupied: Grid<bool>,
+    /// The x of the grid's first column, negative if the floor reaches that far left.
+    origin_x: Num,
     max_y: Num,
     walled: bool,
 }
 
-impl Grid {
-    fn parse(file: &str, walled: bool) -> Self {
-        let mut fields = HashSet::new();
-        let mut lowest = 0;
-        for line in util::read_lines(file) {
-            // println!("Line: {}", line);
-            let path = Path::parse(&line);
-            for point in path.points() {
-                // println!("Pt {:?}", point);
-                if point.y > lowest {
-                    lowest = point.y;
-                }
-                fields.insert(point);
-            }
+impl Cave {
+    fn parse(file: &str) -> Result<Self> {
+        Self::from_paths(&util::lines(file)?.parse(Path::parse)?)
+    }
+
+    fn from_paths(paths: &[Path]) -> Result<Self> {
+        let points: Vec<Point> = paths.iter().flat_map(|path| path.points()).collect();
+        let max_y = points.iter().map(|point| point.y).max().unwrap_or(0);
+        // Sand moves at most one step sideways per step down, so it can't get further from
+        // the source than the floor is deep.
+        let depth = max_y + 3;
+        let origin_x = points
+            .iter()
+            .map(|point| point.x)
+            .fold(500 - depth, Num::min);
+        let max_x = points
+            .iter()
+            .map(|point| point.x)
+            .fold(500 + depth, Num::max);
+        if let Some(point) = points.iter().find(|point| point.y < 0) {
+            return Err(Error::invalid(
+                "Rock above the source of the sand",
+                format!("{},{}", point.x, point.y),
+            ));
         }
-        Self {
-            fields,
-            max_y: lowest,
-            walled,
+        let mut occupied = Grid::new((max_x - origin_x + 1) as usize, depth as usize, false);
+        for point in points {
+            occupied[((point.x - origin_x) as usize, point.y as usize)] = true;
         }
+        Ok(Self {
+            occupied,
+            origin_x,
+            max_y,
+            walled: false,
+        })
     }
 
     fn is_occupied(&self, point: &Point) -> bool {
-        self.fields.get(point).is_some() || (self.walled && point.y == self.max_y + 2)
+        (self.walled && point.y == self.max_y + 2)
+            || self
+                .occupied
+                .to_pos((point.x - self.origin_x) as isize, point.y as isize)
+                .is_some_and(|pos| self.occupied[pos])
     }
 
     fn simulate_one_sand(&mut self) -> bool {
         let mut current = Point { x: 500, y: 0 };
         while current.y < self.max_y + 3 {
-            // println!("Current: {:?}, Max_y: {}", current, self.max_y);
-            let next = current.step(Down);
+            trace!("Sand falls to {:?}", current);
+            let next = current + DOWN;
             if !self.is_occupied(&next) {
                 current = next;
                 continue;
             }
-            let next = current.step(DownLeft);
+            let next = current + DOWN_LEFT;
             if !self.is_occupied(&next) {
                 current = next;
                 continue;
             }
-            let next = current.step(DownRight);
+            let next = current + DOWN_RIGHT;
             if !self.is_occupied(&next) {
                 current = next;
                 continue;
             }
-            return self.fields.insert(current);
+            let cell =
+                &mut self.occupied[((current.x - self.origin_x) as usize, current.y as usize)];
+            trace!("Sand comes to rest at {:?}", current);
+            return !std::mem::replace(cell, true);
         }
         false
     }
@@ -150,21 +143,82 @@ impl Grid {
     fn simulate_all(&mut self) -> usize {
         let mut count = 0;
         while self.simulate_one_sand() {
-            // println!("Simulated {}", count);
             count += 1;
         }
         count
     }
 }
 
-pub fn part_1(file: &str) -> usize {
-    let mut grid = Grid::parse(file, false);
-    // println!("parsed");
-    grid.simulate_all()
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input = Cave;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Cave::parse(file)
+    }
+
+    fn part_1(cave: &Self::Input, _: &()) -> Answer {
+        let mut cave = cave.clone();
+        cave.simulate_all().into()
+    }
+
+    fn part_2(cave: &Self::Input, _: &()) -> Answer {
+        let mut cave = cave.clone();
+        cave.walled = true;
+        cave.simulate_all().into()
+    }
+
+    /// `paths` paths of rock below the source, each of one to three straight segments. The
+    /// cave is half as deep as there are paths, but at least 20 and at most 160 tiles.
+    fn generate(rng: &mut Rng, paths: usize) -> Option<String> {
+        let depth = (paths as Num / 2).clamp(20, 160);
+        let mut res = String::new();
+        for _ in 0..paths {
+            let mut point = Point::new(
+                500 + rng.range(-(depth as i64)..depth as i64 + 1) as Num,
+                rng.range(2..depth as i64 + 1) as Num,
+            );
+            let mut points = vec![point];
+            let horizontal = rng.chance(0.5);
+            for i in 0..rng.range(1..4) {
+                let len = rng.range(1..7) as Num;
+                let len = if rng.chance(0.5) { len } else { -len };
+                if (i % 2 == 0) == horizontal {
+                    point.x += len;
+                } else if (2..=depth).contains(&(point.y + len)) {
+                    point.y += len;
+                } else {
+                    point.y -= len;
+                }
+                points.push(point);
+            }
+            let points: Vec<String> = points
+                .iter()
+                .map(|point| format!("{},{}", point.x, point.y))
+                .collect();
+            res.push_str(&points.join(" -> "));
+            res.push('\n');
+        }
+        Some(res)
+    }
 }
 
-pub fn part_2(file: &str) -> usize {
-    let mut grid = Grid::parse(file, true);
-    // println!("parsed");
-    grid.simulate_all()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deep_cave() {
+        let paths = [
+            Path::parse("499,520 -> 501,520").unwrap(),
+            Path::parse("-20,10 -> -10,10").unwrap(),
+        ];
+        let mut cave = Cave::from_paths(&paths).unwrap();
+        assert!(cave.origin_x < -20);
+        assert!(cave.is_occupied(&Point::new(-15, 10)));
+        assert_eq!(cave.simulate_all(), 1);
+        assert!(Cave::from_paths(&[Path::parse("500,-1").unwrap()]).is_err());
+    }
 }
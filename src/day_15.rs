@@ -1,27 +1,19 @@
 use std::collections::HashSet;
+use std::ops::Range;
 
+use crate::error::Result;
+use crate::log::{debug, trace};
+use crate::solution::{Answer, InputKind, Solution};
 use crate::util;
+use crate::util::geom::Vec2;
+use crate::util::interval::{Interval, IntervalSet};
+use crate::util::parallel;
+use crate::util::pattern;
+use crate::util::rng::Rng;
 
 type Num = i32;
 
-#[derive(Debug)]
-struct Point {
-    x: Num,
-    y: Num,
-}
-
-impl Point {
-    fn distance(&self, other: &Point) -> Num {
-        let dx = (self.x - other.x).abs();
-        let dy = (self.y - other.y).abs();
-        let d = dx + dy;
-        // println!(
-        //     "Distance from {} to {}: dx -> {}, dy -> {}, d -> {}",
-        //     self, other, dx, dy, d
-        // );
-        d
-    }
-}
+type Point = Vec2<Num>;
 
 #[derive(Debug)]
 struct SensorData {
@@ -30,134 +22,231 @@ struct SensorData {
     radius: Num,
 }
 
-#[derive(Clone, Copy, Debug)]
-struct Interval {
-    first: Num,
-    last: Num,
-}
-
 impl SensorData {
-    fn parse(line: &str) -> Self {
-        let line = line
-            .replace(",", "")
-            .replace(":", "")
-            .replace("x=", "")
-            .replace("y=", "");
-        let tokens: Vec<&str> = line.split(" ").collect();
-        let sensor = Point {
-            x: tokens[2].parse().unwrap(),
-            y: tokens[3].parse().unwrap(),
-        };
-        let closest_beacon = Point {
-            x: tokens[8].parse().unwrap(),
-            y: tokens[9].parse().unwrap(),
-        };
-        let radius = (&sensor).distance(&closest_beacon);
-        Self {
+    fn parse(line: &str) -> Result<Self> {
+        let [sensor_x, sensor_y, beacon_x, beacon_y] = pattern::int_array(line)?;
+        let sensor = Point::new(sensor_x, sensor_y);
+        let closest_beacon = Point::new(beacon_x, beacon_y);
+        let radius = sensor.manhattan(closest_beacon);
+        Ok(Self {
             sensor,
             closest_beacon,
             radius,
-        }
+        })
     }
 
-    fn cover_on(&self, line: Num) -> Option<Interval> {
-        let diff_y = (self.sensor.y - line).abs();
-        let radius_on_line = self.radius - diff_y;
-        if radius_on_line >= 0 {
-            Some(Interval {
-                first: self.sensor.x - radius_on_line,
-                last: self.sensor.x + radius_on_line,
-            })
-        } else {
-            None
-        }
+    fn cover_on(&self, line: Num) -> Option<Interval<Num>> {
+        let radius_on_line = self.radius - (self.sensor.y - line).abs();
+        Interval::new(
+            self.sensor.x - radius_on_line,
+            self.sensor.x + radius_on_line,
+        )
     }
 }
 
-struct SensorMap {
+pub struct SensorMap {
     data: Vec<SensorData>,
 }
 
 impl SensorMap {
-    fn parse(file: &str) -> Self {
-        let data = util::read_lines(file)
+    fn parse(file: &str) -> Result<Self> {
+        let data = util::lines(file)?.parse(SensorData::parse)?;
+        Ok(Self { data })
+    }
+
+    fn beacons_on(&self, line: Num) -> HashSet<Num> {
+        self.data
             .iter()
-            .map(|line| SensorData::parse(line))
-            .collect();
-        Self { data }
+            .filter(|sensor_data| sensor_data.closest_beacon.y == line)
+            .map(|sensor_data| sensor_data.closest_beacon.x)
+            .collect()
     }
 
     fn covered_count(&self, line: Num) -> usize {
-        let mut covered = HashSet::new();
-        let beacons_on_line: HashSet<Num> = self
+        let covered: IntervalSet<Num> = self
             .data
             .iter()
-            .filter(|sensor_data| sensor_data.closest_beacon.y == line)
-            .map(|sensor_data| sensor_data.closest_beacon.x)
-            .collect();
-        for sensor_data in &self.data {
-            if let Some(interval) = sensor_data.cover_on(line) {
-                println!(
+            .filter_map(|sensor_data| {
+                let interval = sensor_data.cover_on(line)?;
+                debug!(
                     "Cover of sensor {:?} with radius {} in line {} is {:?}",
                     sensor_data.sensor, sensor_data.radius, line, interval
                 );
-                for x in interval.first..interval.last + 1 {
-                    if !beacons_on_line.get(&x).is_some() {
-                        covered.insert(x);
-                    }
-                }
-            }
-        }
-        covered.len()
+                Some(interval)
+            })
+            .collect();
+        let beacons_covered = self
+            .beacons_on(line)
+            .into_iter()
+            .filter(|x| covered.contains(*x))
+            .count();
+        covered.len() as usize - beacons_covered
     }
 
+    /// The rows are independent of each other, so they are searched in parallel.
     fn find_only_sender(&self, max_xy: Num) -> Point {
-        let mut only_result: Option<Point> = None;
-        'lines: for y in 0..max_xy + 1 {
-            // println!("\n\n y = {}", y);
-            let beacons_on_line: HashSet<Num> = self
+        let candidates = parallel::map_ranges(max_xy as usize + 1, |rows| {
+            self.find_senders(rows.start as Num..rows.end as Num, max_xy)
+        })
+        .concat();
+        match candidates[..] {
+            [] => panic!("No solution found"),
+            [sender] => sender,
+            _ => panic!("More than one possible position!"),
+        }
+    }
+
+    fn find_senders(&self, rows: Range<Num>, max_xy: Num) -> Vec<Point> {
+        let area = Interval {
+            first: 0,
+            last: max_xy,
+        };
+        let mut senders = Vec::new();
+        for y in rows {
+            let covered: IntervalSet<Num> = self
                 .data
                 .iter()
-                .filter(|sensor_data| sensor_data.closest_beacon.y == y)
-                .map(|sensor_data| sensor_data.closest_beacon.x)
+                .filter_map(|sensor_data| sensor_data.cover_on(y))
                 .collect();
-            let mut intervals = Vec::new();
-            for sensor_data in &self.data {
-                if let Some(interval) = sensor_data.cover_on(y) {
-                    // println!("  interval: {:?}", interval);
-                    intervals.push(interval);
-                }
+            trace!("Line {} is covered at {:?}", y, covered.intervals());
+            let uncovered = covered.complement(area);
+            if uncovered.is_empty() {
+                continue;
             }
-            intervals.sort_unstable_by(|i1, i2| i1.first.cmp(&i2.first));
-            let mut next_possible: Num = 0;
-            for Interval { first, last } in intervals.iter() {
-                if next_possible > max_xy {
-                    continue 'lines;
-                }
-                for x in next_possible..*first {
-                    // print!("  x = {}", x);
-                    if beacons_on_line.get(&x).is_none() {
-                        if only_result.is_some() {
-                            panic!("More than one possible position!")
-                        } else {
-                            only_result = Some(Point { x, y })
-                        }
+            let beacons_on_line = self.beacons_on(y);
+            for gap in uncovered.intervals() {
+                for x in gap.first..=gap.last {
+                    if !beacons_on_line.contains(&x) {
+                        senders.push(Point { x, y })
                     }
                 }
-                next_possible = next_possible.max(last + 1)
             }
         }
-        only_result.expect("No solution found")
+        senders
     }
 }
 
-pub fn part_1(file: &str, line: Num) -> usize {
-    let map = SensorMap::parse(file);
-    map.covered_count(line)
+pub struct Config {
+    /// The row to count covered positions on in part 1.
+    row: Num,
+    /// The upper limit of both coordinates of the distress beacon in part 2.
+    max_xy: Num,
 }
 
-pub fn part_2(file: &str, max_xy: Num) -> u64 {
-    let map = SensorMap::parse(file);
-    let Point { x, y } = map.find_only_sender(max_xy);
-    (x as u64) * 4000000 + (y as u64)
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            row: 2000000,
+            max_xy: 4000000,
+        }
+    }
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Input = SensorMap;
+    type Config = Config;
+
+    fn config(kind: InputKind) -> Config {
+        match kind {
+            InputKind::Test => Config {
+                row: 10,
+                max_xy: 20,
+            },
+            InputKind::Puzzle => Config::default(),
+        }
+    }
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        SensorMap::parse(file)
+    }
+
+    fn part_1(map: &Self::Input, config: &Config) -> Answer {
+        map.covered_count(config.row).into()
+    }
+
+    fn part_2(map: &Self::Input, config: &Config) -> Answer {
+        let Point { x, y } = map.find_only_sender(config.max_xy);
+        ((x as u64) * 4000000 + (y as u64)).into()
+    }
+
+    /// `sensors` sensors for the row and the area of the puzzle, but at most 40 like in the
+    /// puzzle, since part 2 goes through all of them on each of four million rows. Four of
+    /// them sit diagonally around the distress beacon, with their closest beacons one step
+    /// closer than it. They are far enough away to cover the rest of the area. The others
+    /// only see beacons that are closer than the distress beacon.
+    fn generate(rng: &mut Rng, sensors: usize) -> Option<String> {
+        let max_xy = Config::default().max_xy as i64;
+        let sender = Point::new(
+            rng.range(0..max_xy + 1) as Num,
+            rng.range(0..max_xy + 1) as Num,
+        );
+        let line = |sensor: Point, beacon: Point| {
+            format!(
+                "Sensor at x={}, y={}: closest beacon is at x={}, y={}\n",
+                sensor.x, sensor.y, beacon.x, beacon.y
+            )
+        };
+        let mut res = String::new();
+        let far = rng.range(max_xy..max_xy + max_xy / 4) as Num;
+        for (dx, dy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let sensor = sender + Point::new(dx * far, dy * far);
+            // Next to the distress beacon, one step closer to the sensor.
+            let beacon = sender + Point::new(dx, 0);
+            res.push_str(&line(sensor, beacon));
+        }
+        for _ in 4..sensors.min(40) {
+            let sensor = Point::new(
+                rng.range(-max_xy / 4..max_xy + max_xy / 4) as Num,
+                rng.range(-max_xy / 4..max_xy + max_xy / 4) as Num,
+            );
+            let Some(radius) = sensor.manhattan(sender).checked_sub(1).filter(|r| *r > 0) else {
+                continue;
+            };
+            let radius = rng.range(1..radius as i64 + 1) as Num;
+            let dx = rng.range(-(radius as i64)..radius as i64 + 1) as Num;
+            let dy = if rng.chance(0.5) {
+                radius - dx.abs()
+            } else {
+                dx.abs() - radius
+            };
+            res.push_str(&line(sensor, sensor + Point::new(dx, dy)));
+        }
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covered_by_sensor(map: &SensorMap, point: Point) -> bool {
+        map.data
+            .iter()
+            .any(|sensor_data| sensor_data.sensor.manhattan(point) <= sensor_data.radius)
+    }
+
+    fn is_beacon(map: &SensorMap, point: Point) -> bool {
+        map.data
+            .iter()
+            .any(|sensor_data| sensor_data.closest_beacon == point)
+    }
+
+    #[test]
+    fn test_against_positions() {
+        let map = SensorMap::parse("15-test").unwrap();
+        for y in -15..35 {
+            let covered = (-20..50)
+                .map(|x| Point::new(x, y))
+                .filter(|point| covered_by_sensor(&map, *point) && !is_beacon(&map, *point))
+                .count();
+            assert_eq!(map.covered_count(y), covered, "line {}", y);
+        }
+        let senders: Vec<Point> = (0..=20)
+            .flat_map(|y| (0..=20).map(move |x| Point::new(x, y)))
+            .filter(|point| !covered_by_sensor(&map, *point) && !is_beacon(&map, *point))
+            .collect();
+        assert_eq!(map.find_senders(0..21, 20), senders);
+    }
 }
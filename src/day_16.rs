@@ -1,6 +1,13 @@
 use std::collections::HashMap;
 
+use crate::error::{Error, Result};
+use crate::log::{debug, trace};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::parallel;
+use crate::util::pattern;
+use crate::util::rng::Rng;
+use crate::util::search;
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 struct SearchState {
@@ -9,15 +16,15 @@ struct SearchState {
     current_valve: usize,
 }
 
-#[derive(Debug)]
-struct CaveSystem {
+#[derive(Clone, Debug)]
+pub struct CaveSystem {
     valve_names: Vec<String>,
     flow_rates: Vec<usize>,
     links: Vec<Vec<usize>>,
-    shortest_path: Vec<Vec<usize>>,
+    /// The fewest steps between two valves, `None` if there is no way.
+    shortest_path: Vec<Vec<Option<usize>>>,
     valve_count: usize,
     cache: HashMap<SearchState, usize>,
-    // debug: HashMap<usize, usize>,
 }
 
 impl CaveSystem {
@@ -38,23 +45,14 @@ impl CaveSystem {
         }
     }
 
-    fn find_shortest_paths(&mut self, source: usize) {
-        self.shortest_path[source][source] = 0;
-        self.rec_find_path(0, source, source);
+    fn find_shortest_paths(&self, source: usize) -> Vec<Option<usize>> {
+        let distances = search::distances([source], |valve| self.links[*valve].clone());
+        (0..self.valve_count)
+            .map(|target| distances.get(&target).copied())
+            .collect()
     }
 
-    fn rec_find_path(&mut self, len: usize, source: usize, current: usize) {
-        let len = len + 1;
-        let next_items = self.links[current].clone();
-        for next in &next_items {
-            if len < self.shortest_path[source][*next] {
-                self.shortest_path[source][*next] = len;
-                self.rec_find_path(len, source, *next);
-            }
-        }
-    }
-
-    fn parse(file: &str) -> Self {
+    fn parse(file: &str) -> Result<Self> {
         let mut system = Self {
             valve_names: Vec::new(),
             flow_rates: Vec::new(),
@@ -62,53 +60,50 @@ impl CaveSystem {
             shortest_path: Vec::new(),
             valve_count: 0,
             cache: HashMap::new(),
-            // debug: HashMap::new(),
         };
 
         system.valve_index("AA");
 
-        for line in util::read_lines(file) {
-            let tokens: Vec<&str> = line.split_whitespace().collect();
-            let valve = system.valve_index(tokens[1]);
-            let rate = tokens[4]
-                .replace("rate=", "")
-                .replace(";", "")
-                .parse()
-                .expect("Failed to parse rate");
+        util::lines(file)?.parse(|line| {
+            let [rate] = pattern::int_array(line)?;
+            let mut names =
+                pattern::words(line).filter(|word| word.chars().all(|c| c.is_ascii_uppercase()));
+            let valve =
+                system.valve_index(names.next().ok_or_else(|| Error::new("Missing valve"))?);
             system.flow_rates[valve] = rate;
-            for linked_valve in &tokens[9..] {
-                let target = system.valve_index(&linked_valve.replace(",", ""));
+            for linked_valve in names {
+                let target = system.valve_index(linked_valve);
                 system.links[valve].push(target);
             }
-            system.shortest_path = vec![vec![9999; system.valve_count]; system.valve_count];
-            for source in 0..system.valve_count {
-                system.find_shortest_paths(source)
-            }
-        }
-        system
+            Ok(())
+        })?;
+        system.shortest_path = (0..system.valve_count)
+            .map(|source| system.find_shortest_paths(source))
+            .collect();
+        system.log_shortest_paths();
+        Ok(system)
     }
 
-    #[allow(dead_code)]
-    fn print_shortest_paths(&self) {
+    fn log_shortest_paths(&self) {
         for source in 0..self.valve_count {
             let src = &self.valve_names[source];
             for target in 0..self.valve_count {
                 let tgt = &self.valve_names[target];
-                println!(
-                    "{} => {}\t{} min",
-                    src, tgt, self.shortest_path[source][target],
-                );
+                match self.shortest_path[source][target] {
+                    Some(len) => trace!("{} => {}\t{} min", src, tgt, len),
+                    None => trace!("{} => {}\tunreachable", src, tgt),
+                }
             }
         }
     }
 
-    fn find_max_flow(&mut self, time_limit: usize, limit_valves: Option<Vec<usize>>) -> usize {
+    fn find_max_flow(&mut self, time_limit: usize, limit_valves: Option<&[usize]>) -> usize {
         let is_open = match limit_valves {
             None => vec![false; self.valve_count],
             Some(valves) => {
                 let mut is_open = vec![true; self.valve_count];
                 for valve in valves {
-                    is_open[valve] = false;
+                    is_open[*valve] = false;
                 }
                 is_open
             }
@@ -117,7 +112,7 @@ impl CaveSystem {
         let start_valve = self.valve_index("AA");
         let root = SearchState {
             time_remaining: time_limit,
-            is_open: is_open,
+            is_open,
             current_valve: start_valve,
         };
         self.rec_find_max_flow(root)
@@ -131,18 +126,11 @@ impl CaveSystem {
         let mut max_flow = 0;
 
         for valve in 0..self.valve_count {
-            // if !self.debug.is_empty() {
-            //     match self.debug.get(&(&state.time_remaining)) {
-            //         None => continue,
-            //         Some(v) => {
-            //             if *v != valve {
-            //                 continue;
-            //             }
-            //         }
-            //     }
-            // }
             if !state.is_open[valve] && self.flow_rates[valve] > 0 {
-                let travel_time = self.shortest_path[state.current_valve][valve] + 1;
+                let Some(distance) = self.shortest_path[state.current_valve][valve] else {
+                    continue;
+                };
+                let travel_time = distance + 1;
                 if state.time_remaining > travel_time {
                     let time_remaining = state.time_remaining - travel_time;
                     let mut state = state.clone();
@@ -150,12 +138,14 @@ impl CaveSystem {
                     state.is_open[valve] = true;
                     state.time_remaining = time_remaining;
                     let produced_flow = self.flow_rates[valve] * time_remaining;
-                    // println!(
-                    //     "Opened {} and produced  {} x {} = {}",
-                    //     valve, time_remaining, self.flow_rates[valve], produced_flow
-                    // );
+                    trace!(
+                        "Opened {} and produced {} x {} = {}",
+                        self.valve_names[valve],
+                        time_remaining,
+                        self.flow_rates[valve],
+                        produced_flow
+                    );
                     let flow = produced_flow + self.rec_find_max_flow(state);
-                    // println!("Total flow: {}", flow);
                     if flow > max_flow {
                         max_flow = flow;
                     }
@@ -163,10 +153,10 @@ impl CaveSystem {
             }
         }
         self.cache.insert(state, max_flow);
-        return max_flow;
+        max_flow
     }
 
-    fn calc_partitions(&mut self) -> Vec<(Vec<usize>, Vec<usize>)> {
+    fn calc_partitions(&self) -> Vec<(Vec<usize>, Vec<usize>)> {
         let mut valves: Vec<usize> = (0..self.valve_count)
             .filter(|valve| self.flow_rates[*valve] > 0)
             .collect();
@@ -192,35 +182,93 @@ impl CaveSystem {
     }
 }
 
-fn part_1(file: &str) {
-    let mut system = CaveSystem::parse(file);
-    println!("Part 1: {}", system.find_max_flow(30, None));
-}
+pub struct Day16;
+
+impl Solution for Day16 {
+    type Input = CaveSystem;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        CaveSystem::parse(file)
+    }
 
-fn part_2(file: &str) {
-    let mut system = CaveSystem::parse(file);
-    let partitions = system.calc_partitions();
-    let mut best = 0;
-    let partition_count = partitions.len();
-    println!("Partitions to do: {}", partition_count);
-    let mut i = 0;
-    for (human_valves, elephant_valves) in partitions {
-        i += 1;
-        if i % 100 == 0 {
-            println!("Done {} / {}", i, partition_count);
+    fn part_1(system: &Self::Input, _: &()) -> Answer {
+        let mut system = system.clone();
+        system.find_max_flow(30, None).into()
+    }
+
+    fn part_2(system: &Self::Input, _: &()) -> Answer {
+        let partitions = system.calc_partitions();
+        let partition_count = partitions.len();
+        debug!("Partitions to do: {}", partition_count);
+        // The partitions are independent, every thread just needs its own cache.
+        parallel::map_chunks(&partitions, |partitions| {
+            let mut system = system.clone();
+            let mut best = 0;
+            for (i, (human_valves, elephant_valves)) in partitions.iter().enumerate() {
+                if (i + 1) % 100 == 0 {
+                    debug!("Done {} / {}", i + 1, partitions.len());
+                }
+                let human_flow = system.find_max_flow(26, Some(human_valves));
+                let elephant_flow = system.find_max_flow(26, Some(elephant_valves));
+                best = best.max(human_flow + elephant_flow);
+            }
+            best
+        })
+        .into_iter()
+        .max()
+        .unwrap()
+        .into()
+    }
+
+    /// `valves` valves connected like a tree with some extra tunnels, at most 676 so that
+    /// two letters can name them. Part 2 tries every way to split the valves with a flow, so
+    /// at most ten of them have one.
+    fn generate(rng: &mut Rng, valves: usize) -> Option<String> {
+        let valves = valves.clamp(2, 26 * 26);
+        let name = |valve: usize| {
+            [valve / 26, valve % 26]
+                .iter()
+                .map(|letter| char::from(b'A' + *letter as u8))
+                .collect::<String>()
+        };
+        let mut links = vec![Vec::new(); valves];
+        let mut link = |a: usize, b: usize| {
+            if a != b && !links[a].contains(&b) {
+                links[a].push(b);
+                links[b].push(a);
+            }
+        };
+        for valve in 1..valves {
+            link(rng.index(valve), valve);
         }
-        let human_flow = system.find_max_flow(26, Some(human_valves));
-        let elephant_flow = system.find_max_flow(26, Some(elephant_valves));
-        let total = human_flow + elephant_flow;
-        if total > best {
-            best = total;
+        for _ in 0..valves / 4 {
+            link(rng.index(valves), rng.index(valves));
         }
+        let mut rates = vec![0; valves];
+        let mut with_flow: Vec<usize> = (1..valves).collect();
+        rng.shuffle(&mut with_flow);
+        for valve in with_flow.into_iter().take(10.min(valves / 2).max(1)) {
+            rates[valve] = rng.range(1..26);
+        }
+        Some(
+            (0..valves)
+                .map(|valve| {
+                    let targets: Vec<String> = links[valve].iter().map(|t| name(*t)).collect();
+                    let tunnels = if targets.len() == 1 {
+                        "tunnel leads to valve"
+                    } else {
+                        "tunnels lead to valves"
+                    };
+                    format!(
+                        "Valve {} has flow rate={}; {} {}\n",
+                        name(valve),
+                        rates[valve],
+                        tunnels,
+                        targets.join(", ")
+                    )
+                })
+                .collect(),
+        )
     }
-    println!("Part 2: {}", best);
-}
-
-pub fn run() {
-    let file = "16-input";
-    part_1(file);
-    part_2(file);
 }
@@ -1,24 +1,15 @@
-use std::ops::Add;
-
+use crate::error::{Error, Result};
+use crate::log::trace;
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::cycle;
+use crate::util::geom::Vec2;
+use crate::util::rng::Rng;
+use crate::util::Grid;
 
 type Coord = i32;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-struct Point {
-    x: Coord,
-    y: Coord,
-}
-
-impl Add for Point {
-    type Output = Self;
-    fn add(self, other: Self) -> Self {
-        Point {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        }
-    }
-}
+type Point = Vec2<Coord>;
 
 #[derive(Clone, Debug)]
 struct Rock {
@@ -26,7 +17,7 @@ struct Rock {
 }
 
 impl Rock {
-    fn parse(lines: &Vec<String>) -> Self {
+    fn parse(lines: &[String]) -> Self {
         let mut points = vec![];
         for (y, line) in lines.iter().rev().enumerate() {
             for (x, char) in line.chars().enumerate() {
@@ -43,48 +34,53 @@ impl Rock {
 
     fn raw_move(&mut self, direction: Point) {
         for point in &mut self.points {
-            *point = *point + direction
+            *point += direction
         }
     }
 }
 
 const WIDTH: Coord = 7;
 
-struct Chamber {
+/// How many rows from the top make up the surface that decides where the next rocks land.
+const SURFACE_ROWS: usize = 32;
+
+#[derive(Clone)]
+pub struct Chamber {
     rocks: Vec<Rock>,
     rock_index: usize,
     directions: Vec<Point>,
     direction_index: usize,
     heighest: Coord,
-    occupied: Vec<Vec<bool>>,
-    directions_history: Vec<usize>,
-    growth_history: Vec<Coord>,
+    occupied: Grid<bool>,
 }
 
 impl Chamber {
-    fn parse(rock_file: &str, input_file: &str) -> Self {
-        let rocks: Vec<Rock> = util::read_blocks(rock_file)
+    fn parse(rock_file: &str, input_file: &str) -> Result<Self> {
+        let rocks: Vec<Rock> = util::read_blocks(rock_file)?
             .iter()
-            .map(Rock::parse)
+            .map(|block| Rock::parse(block))
             .collect();
-        let directions: Vec<Point> = util::read_str(input_file)
+        let directions: Vec<Point> = util::read_str(input_file)?
+            .trim_end()
             .chars()
-            .map(|c| match c {
-                '<' => Point { x: -1, y: 0 },
-                '>' => Point { x: 1, y: 0 },
-                _ => panic!("Illegal direction: {}", c),
+            .enumerate()
+            .map(|(i, c)| match c {
+                '<' => Ok(Point { x: -1, y: 0 }),
+                '>' => Ok(Point { x: 1, y: 0 }),
+                _ => Err(Error::invalid("Illegal direction", c).at_column(i + 1)),
             })
-            .collect();
-        Self {
+            .collect::<Result<_>>()?;
+        if directions.is_empty() {
+            return Err(Error::new("No jet directions"));
+        }
+        Ok(Self {
             rocks,
             rock_index: 0,
             directions,
             direction_index: 0,
             heighest: -1,
-            occupied: Vec::new(),
-            directions_history: Vec::new(),
-            growth_history: Vec::new(),
-        }
+            occupied: Grid::new(WIDTH as usize, 0, false),
+        })
     }
 
     fn next_rock(&mut self) -> Rock {
@@ -98,58 +94,51 @@ impl Chamber {
     }
 
     fn next_direction(&mut self) -> Point {
-        let direction = self.directions[self.direction_index].clone();
+        let direction = self.directions[self.direction_index];
         self.direction_index = (self.direction_index + 1) % self.directions.len();
         direction
     }
 
     fn try_move(&self, rock: &mut Rock, direction: Point) -> bool {
-        // println!("Try move {:?}", direction);
         let mut next_rock = rock.clone();
         next_rock.raw_move(direction);
         for p in &next_rock.points {
-            if p.x < 0 || p.y < 0 || p.x >= WIDTH {
-                // println!("Out of field!");
+            if p.x < 0
+                || p.y < 0
+                || p.x >= WIDTH
+                || self.occupied.get((p.x as usize, p.y as usize)) == Some(&true)
+            {
                 return false;
-            } else if let Some(row) = self.occupied.get(p.y as usize) {
-                if row[p.x as usize] {
-                    return false;
-                }
             }
         }
         *rock = next_rock;
-        return true;
+        true
     }
 
     fn drop_rock(&mut self) {
         let mut rock = self.next_rock();
         loop {
-            // println!("\nBefore side");
-            // self.print(Some(&rock));
             let dir = self.next_direction();
             self.try_move(&mut rock, dir);
-            // self.print(Some(&rock));
+            trace!("After a push by {:?}:\n{}", dir, self.render(Some(&rock)));
             if !self.try_move(&mut rock, Point { x: 0, y: -1 }) {
-                let prev_height = self.heighest;
                 for p in &rock.points {
-                    if p.y as usize == self.occupied.len() {
-                        self.occupied.push(vec![false; WIDTH as usize]);
+                    if p.y as usize == self.occupied.height() {
+                        self.occupied.push_row(vec![false; WIDTH as usize]);
                     }
-                    self.occupied[p.y as usize][p.x as usize] = true;
+                    self.occupied[(p.x as usize, p.y as usize)] = true;
                     if p.y > self.heighest {
                         self.heighest = p.y
                     }
                 }
-                self.growth_history.push(self.heighest - prev_height);
-                self.directions_history.push(self.direction_index);
                 return;
             }
         }
     }
 
-    #[allow(dead_code)]
-    fn print(&self, rock: Option<&Rock>) {
-        println!();
+    /// Draws the chamber from the top, with the falling rock if any.
+    fn render(&self, rock: Option<&Rock>) -> String {
+        let mut s = String::new();
         let start_y = if let Some(rock) = rock {
             rock.points.iter().map(|p| p.y).max().unwrap()
         } else {
@@ -162,86 +151,72 @@ impl Chamber {
                 } else {
                     false
                 };
-                let on_occupied = if let Some(row) = self.occupied.get(y as usize) {
-                    row[x as usize]
-                } else {
-                    false
-                };
+                let on_occupied = self.occupied.get((x as usize, y as usize)) == Some(&true);
                 if on_rock {
-                    print!("@")
+                    s.push('@')
                 } else if on_occupied {
-                    print!("#")
+                    s.push('#')
                 } else {
-                    print!(".")
+                    s.push('.')
                 }
             }
-            println!()
+            s.push('\n')
         }
+        s
     }
 
-    fn check_for_cycle(&self) -> Option<usize> {
-        let total_rocks = self.growth_history.len();
-        let rock_cycle_len = self.rocks.len();
-        let compare_seq_len = 20 * rock_cycle_len;
-        if self.rock_index == 0 && total_rocks >= compare_seq_len + rock_cycle_len {
-            let last_sequence_start = total_rocks - compare_seq_len;
-            let last_growth_sequence = &self.growth_history[last_sequence_start..total_rocks];
-            let last_directions_sequence =
-                &self.directions_history[last_sequence_start..total_rocks];
-            let mut sequence_start = last_sequence_start - rock_cycle_len;
-            loop {
-                if &self.growth_history[sequence_start..sequence_start + compare_seq_len]
-                    == last_growth_sequence
-                    && &self.directions_history[sequence_start..sequence_start + compare_seq_len]
-                        == last_directions_sequence
-                {
-                    return Some(last_sequence_start - sequence_start);
-                } else if sequence_start < rock_cycle_len {
-                    break;
-                } else {
-                    sequence_start -= rock_cycle_len
-                }
-            }
-        }
-        None
+    /// The next rock, the next jet and the top rows, one bit per tile. Once these repeat, so
+    /// does the growth of the tower.
+    fn state_key(&self) -> (usize, usize, Vec<u8>) {
+        let surface = (0..self.occupied.height())
+            .rev()
+            .take(SURFACE_ROWS)
+            .map(|y| {
+                self.occupied
+                    .row(y)
+                    .iter()
+                    .fold(0, |bits, occupied| bits << 1 | *occupied as u8)
+            })
+            .collect();
+        (self.rock_index, self.direction_index, surface)
+    }
+
+    fn height(&self) -> u64 {
+        (self.heighest + 1) as u64
     }
 }
 
-pub fn run() {
-    // let mut chamber = Chamber::parse("17-rocks", "17-test");
-    let mut chamber = Chamber::parse("17-rocks", "17-input");
-    for _ in 0..2022 {
-        chamber.drop_rock();
-        // chamber.print(None);
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Input = Chamber;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Chamber::parse("17-rocks", file)
     }
-    println!("Part 1: {}", chamber.heighest + 1);
 
-    let cycle_len;
+    fn part_1(chamber: &Self::Input, _: &()) -> Answer {
+        let mut chamber = chamber.clone();
+        for _ in 0..2022 {
+            chamber.drop_rock();
+        }
+        (chamber.heighest + 1).into()
+    }
 
-    loop {
-        // if chamber.growth_history.len() % chamber.ro {
-        //     return;
-        // }
-        if let Some(len) = chamber.check_for_cycle() {
-            cycle_len = len;
-            break;
+    fn part_2(chamber: &Self::Input, _: &()) -> Answer {
+        let mut chamber = chamber.clone();
+        let mut detector = cycle::Detector::new();
+        loop {
+            if let Some(cycle) = detector.record(chamber.state_key(), chamber.height()) {
+                return detector.extrapolate(cycle, 1000000000000).into();
+            }
+            chamber.drop_rock();
         }
-        chamber.drop_rock();
     }
 
-    let rocks_to_drop = 1000000000000usize;
-    let mut dropped_rocks = chamber.growth_history.len();
-    let cycle = &chamber.growth_history[dropped_rocks - cycle_len..dropped_rocks];
-    let cycle_height = cycle.iter().sum::<i32>() as u64;
-    let mut height = (chamber.heighest + 1) as u64;
-    let left_cycles = (rocks_to_drop - dropped_rocks) / cycle_len;
-    dropped_rocks += cycle_len * left_cycles;
-    height += cycle_height * left_cycles as u64;
-    let offset = dropped_rocks % cycle_len;
-    // println!("offset {}", offset);
-    while dropped_rocks < rocks_to_drop {
-        height += cycle[(dropped_rocks - offset) % cycle_len] as u64;
-        dropped_rocks += 1;
+    fn generate(rng: &mut Rng, jets: usize) -> Option<String> {
+        let jets: String = (0..jets.max(1)).map(|_| *rng.choose(&['<', '>'])).collect();
+        Some(jets + "\n")
     }
-    println!("Part 2: {}", height);
 }
@@ -1,96 +1,41 @@
 use std::collections::HashSet;
 
+use crate::error::{Error, Result};
+use crate::log::trace;
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::geom::{Bounds, Vec3};
+use crate::util::rng::Rng;
 
-type Coord = i32;
-#[derive(Clone, Copy, Debug, PartialOrd, Ord, Hash, PartialEq, Eq)]
-struct Point3D {
-    x: Coord,
-    y: Coord,
-    z: Coord,
-}
-
-impl Point3D {
-    fn parse(line: &String) -> Self {
-        let tokens: Vec<Coord> = line.split(",").map(|x| x.parse().unwrap()).collect();
-        Self {
-            x: tokens[0],
-            y: tokens[1],
-            z: tokens[2],
-        }
-    }
+type Point3D = Vec3;
 
-    fn neighbours(&self) -> Vec<Self> {
-        let Point3D { x, y, z } = self;
-        vec![
-            Point3D {
-                x: x - 1,
-                y: *y,
-                z: *z,
-            },
-            Point3D {
-                x: x + 1,
-                y: *y,
-                z: *z,
-            },
-            Point3D {
-                x: *x,
-                y: y - 1,
-                z: *z,
-            },
-            Point3D {
-                x: *x,
-                y: y + 1,
-                z: *z,
-            },
-            Point3D {
-                x: *x,
-                y: *y,
-                z: z - 1,
-            },
-            Point3D {
-                x: *x,
-                y: *y,
-                z: z + 1,
-            },
-        ]
+fn parse_point(line: &str) -> Result<Point3D> {
+    let tokens: Vec<i32> = line
+        .split(',')
+        .map(util::parse_token)
+        .collect::<Result<_>>()?;
+    match tokens[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(Error::invalid("Expected three coordinates", line)),
     }
 }
 
-struct LavaMap {
+pub struct LavaMap {
     blocks: HashSet<Point3D>,
-    min_x: Coord,
-    max_x: Coord,
-    min_y: Coord,
-    max_y: Coord,
-    min_z: Coord,
-    max_z: Coord,
+    bounds: Bounds<Point3D>,
 }
 
 impl LavaMap {
-    fn parse(file: &str) -> Self {
-        let blocks: HashSet<Point3D> = util::read_lines(file).iter().map(Point3D::parse).collect();
-        let min_x = blocks.iter().map(|p| p.x).min().unwrap();
-        let max_x = blocks.iter().map(|p| p.x).max().unwrap();
-        let min_y = blocks.iter().map(|p| p.y).min().unwrap();
-        let max_y = blocks.iter().map(|p| p.y).max().unwrap();
-        let min_z = blocks.iter().map(|p| p.z).min().unwrap();
-        let max_z = blocks.iter().map(|p| p.z).max().unwrap();
-        Self {
-            blocks,
-            min_x,
-            max_x,
-            min_y,
-            max_y,
-            min_z,
-            max_z,
-        }
+    fn parse(file: &str) -> Result<Self> {
+        let blocks: HashSet<Point3D> = util::lines(file)?.parse(parse_point)?.into_iter().collect();
+        let bounds = Bounds::of(blocks.iter().copied()).ok_or_else(|| Error::new("No cubes"))?;
+        Ok(Self { blocks, bounds })
     }
 
     fn list_surfaces(&self) -> Vec<(Point3D, Point3D)> {
         let mut res = Vec::new();
         for block in &self.blocks {
-            for neighbour in block.neighbours() {
+            for neighbour in block.neighbours_6() {
                 if !self.blocks.contains(&neighbour) {
                     res.push((*block, neighbour));
                 }
@@ -103,65 +48,23 @@ impl LavaMap {
         self.list_surfaces().len()
     }
 
+    /// The points just outside of the bounds.
     fn bounding_box(&self) -> HashSet<Point3D> {
+        let Bounds { min, max } = self.bounds.grow(1);
         let mut res = HashSet::new();
-        let x_range = self.min_x..self.max_x + 1;
-        let y_range = self.min_y..self.max_y + 1;
-        let z_range = self.min_z..self.max_z + 1;
-        for x in x_range.clone() {
-            for y in y_range.clone() {
-                res.insert(Point3D {
-                    x,
-                    y,
-                    z: self.min_z - 1,
-                });
-                res.insert(Point3D {
-                    x,
-                    y,
-                    z: self.max_z + 1,
-                });
-            }
-        }
-        for x in x_range.clone() {
-            for z in z_range.clone() {
-                res.insert(Point3D {
-                    x,
-                    y: self.min_y - 1,
-                    z,
-                });
-                res.insert(Point3D {
-                    x,
-                    y: self.max_y + 1,
-                    z,
-                });
-            }
-        }
-        for z in z_range.clone() {
-            for y in y_range.clone() {
-                res.insert(Point3D {
-                    x: self.min_x - 1,
-                    y,
-                    z,
-                });
-                res.insert(Point3D {
-                    x: self.max_x + 1,
-                    y,
-                    z,
-                });
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let point = Vec3::new(x, y, z);
+                    if !self.bounds.contains(point) {
+                        res.insert(point);
+                    }
+                }
             }
         }
         res
     }
 
-    fn on_map(&self, p: &Point3D) -> bool {
-        p.x >= self.min_x
-            && p.y >= self.min_y
-            && p.z >= self.min_z
-            && p.x <= self.max_x
-            && p.y <= self.max_y
-            && p.z <= self.max_z
-    }
-
     fn count_outer_surfaces(&self) -> usize {
         let mut checked: HashSet<Point3D> = HashSet::new();
         let mut surfaces = 0;
@@ -174,11 +77,10 @@ impl LavaMap {
                 if checked.contains(point) {
                     continue;
                 }
-                // println!("Checking point {:?}", point);
-                for neighbour in point.neighbours() {
-                    // println!("Checking neighbour {:?}", neighbour);
+                trace!("Checking point {:?}", point);
+                for neighbour in point.neighbours_6() {
                     if self.blocks.contains(&neighbour) {
-                        // println!("Found {:?} -> {:?}", point, neighbour);
+                        trace!("Found surface {:?} -> {:?}", point, neighbour);
                         if simple_surfaces
                             .iter()
                             .find(|(block, empty)| empty == point && *block == neighbour)
@@ -187,7 +89,7 @@ impl LavaMap {
                             panic!("Should't contain {:?} -> {:?}", point, neighbour);
                         }
                         surfaces += 1;
-                    } else if !checked.contains(&neighbour) && self.on_map(&neighbour) {
+                    } else if !checked.contains(&neighbour) && self.bounds.contains(neighbour) {
                         check_next.insert(neighbour);
                     }
                 }
@@ -200,10 +102,39 @@ impl LavaMap {
     }
 }
 
-pub fn run() {
-    // let map = LavaMap::parse("18-test");
-    let map = LavaMap::parse("18-input");
-    println!("Part 1: {}", map.count_surfaces());
+pub struct Day18;
+
+impl Solution for Day18 {
+    type Input = LavaMap;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        LavaMap::parse(file)
+    }
+
+    fn part_1(map: &Self::Input, _: &()) -> Answer {
+        map.count_surfaces().into()
+    }
 
-    println!("Part 2: {}", map.count_outer_surfaces());
+    fn part_2(map: &Self::Input, _: &()) -> Answer {
+        map.count_outer_surfaces().into()
+    }
+
+    /// Distinct cubes filling about half of a box.
+    fn generate(rng: &mut Rng, cubes: usize) -> Option<String> {
+        let side = ((2 * cubes) as f64).cbrt().ceil() as i64 + 1;
+        let mut seen = HashSet::new();
+        let mut res = String::new();
+        while seen.len() < cubes.max(1) {
+            let cube = Vec3::new(
+                rng.range(1..side + 1),
+                rng.range(1..side + 1),
+                rng.range(1..side + 1),
+            );
+            if seen.insert(cube) {
+                res.push_str(&format!("{},{},{}\n", cube.x, cube.y, cube.z));
+            }
+        }
+        Some(res)
+    }
 }
@@ -1,6 +1,10 @@
-use std::collections::HashMap;
-
+use crate::error::Result;
+use crate::log::{debug, trace};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::parallel;
+use crate::util::pattern;
+use crate::util::rng::Rng;
 
 type Amount = usize;
 
@@ -32,7 +36,7 @@ impl Resources {
 impl std::ops::Add for Resources {
     type Output = Resources;
     fn add(self, other: Self) -> Self::Output {
-        let mut res = self.clone();
+        let mut res = self;
         for i in 0..ELEMENTS {
             res.items[i] += other.items[i];
         }
@@ -43,7 +47,7 @@ impl std::ops::Add for Resources {
 impl std::ops::Sub for Resources {
     type Output = Resources;
     fn sub(self, other: Self) -> Self::Output {
-        let mut res = self.clone();
+        let mut res = self;
         for i in 0..ELEMENTS {
             res.items[i] -= other.items[i];
         }
@@ -67,27 +71,28 @@ impl Action {
 }
 
 #[derive(Debug)]
-struct Blueprint {
+pub struct Blueprint {
     actions: Vec<Action>,
     max_required: Resources,
 }
 
 impl Blueprint {
-    fn parse(line: &String) -> Self {
-        let tokens: Vec<&str> = line.split_whitespace().collect();
+    fn parse(line: &str) -> Result<Self> {
+        let [_id, ore_ore, clay_ore, obs_ore, obs_clay, geode_ore, geode_obs] =
+            pattern::int_array::<Amount, 7>(line)?;
         let mut prod_ore = Action::new();
-        prod_ore.required_resources.items[ORE] = tokens[6].parse().unwrap();
+        prod_ore.required_resources.items[ORE] = ore_ore;
         prod_ore.production_increase.items[ORE] = 1;
         let mut prod_clay = Action::new();
-        prod_clay.required_resources.items[ORE] = tokens[12].parse().unwrap();
+        prod_clay.required_resources.items[ORE] = clay_ore;
         prod_clay.production_increase.items[CLAY] = 1;
         let mut prod_obs = Action::new();
-        prod_obs.required_resources.items[ORE] = tokens[18].parse().unwrap();
-        prod_obs.required_resources.items[CLAY] = tokens[21].parse().unwrap();
+        prod_obs.required_resources.items[ORE] = obs_ore;
+        prod_obs.required_resources.items[CLAY] = obs_clay;
         prod_obs.production_increase.items[OBSIDIAN] = 1;
         let mut prod_geode = Action::new();
-        prod_geode.required_resources.items[ORE] = tokens[27].parse().unwrap();
-        prod_geode.required_resources.items[OBSIDIAN] = tokens[30].parse().unwrap();
+        prod_geode.required_resources.items[ORE] = geode_ore;
+        prod_geode.required_resources.items[OBSIDIAN] = geode_obs;
         prod_geode.production_increase.items[GEODE] = 1;
         // let actions = vec![prod_ore, prod_clay, prod_obs, prod_geode];
         let actions = vec![prod_geode, prod_obs, prod_clay, prod_ore];
@@ -100,13 +105,13 @@ impl Blueprint {
             }
         }
 
-        Self {
+        Ok(Self {
             actions,
             max_required,
-        }
+        })
     }
-    fn parse_all(file: &str) -> Vec<Self> {
-        util::read_lines(file).iter().map(Self::parse).collect()
+    fn parse_all(file: &str) -> Result<Vec<Self>> {
+        util::lines(file)?.parse(Self::parse)
     }
 }
 
@@ -167,7 +172,7 @@ impl<'a> Search<'a> {
         if state.minute == self.time_available {
             if state.resources.items[GEODE] > self.max_geodes {
                 self.max_geodes = state.resources.items[GEODE];
-                // println!("New max: {}, History: {:?}", self.max_geodes, state.history)
+                trace!("New max: {}, history: {:?}", self.max_geodes, state.history);
             }
             false
         } else {
@@ -204,19 +209,24 @@ impl<'a> Search<'a> {
     fn find_max_geodes(&mut self, state: &SearchState) {
         let max_possible = self.max_possible_geodes(state);
         if max_possible < self.max_geodes {
-            // println!(
-            //     "Aborting at time {}, max possible: {}",
-            //     state.minute, max_possible
-            // );
+            trace!(
+                "Aborting at minute {}, max possible: {}",
+                state.minute,
+                max_possible
+            );
             return;
         }
         // if let Some(geodes) = self.cache.get(state) {
         //     return *geodes;
         // }
-        // println!("Search {}min, {:?} prod", state.minute, state.production);
+        trace!(
+            "Search at minute {}, production {:?}",
+            state.minute,
+            state.production
+        );
         'action: for action in &self.blueprint.actions {
             if self.should_eventually_apply(state, action) {
-                // println!("Awaiting action {:?}", action.production_increase);
+                trace!("Awaiting action {:?}", action.production_increase);
                 let mut state = state.clone();
                 state.history.push(
                     action
@@ -242,30 +252,80 @@ impl<'a> Search<'a> {
     }
 }
 
-pub fn run() {
-    let blueprints = Blueprint::parse_all("19-input");
-    // let blueprints = Blueprint::parse_all("19-input");
-    let quality_sum: usize = blueprints
-        .iter()
-        .enumerate()
-        .map(|(i, blueprint)| {
-            println!("Blueprint {}", i + 1);
-            // println!("Max: {:?}", blueprint.max_required);
-            let res = Search::run(blueprint, 24);
-            println!("Res: {}", res);
-            (i + 1) * res
-        })
-        .sum();
-    println!("Part 1: {}", quality_sum);
-    let geode_product: usize = blueprints
-        .iter()
-        .take(3)
-        .map(|blueprint| {
-            println!("Blueprint");
-            let res = Search::run(blueprint, 32);
-            println!("Res: {}", res);
-            res
-        })
-        .product();
-    println!("Part 2: {}", geode_product);
+pub struct Config {
+    /// Minutes available for each blueprint in part 1.
+    minutes_1: usize,
+    /// Minutes available in part 2.
+    minutes_2: usize,
+    /// How many blueprints the elephants left intact for part 2.
+    blueprints_2: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            minutes_1: 24,
+            minutes_2: 32,
+            blueprints_2: 3,
+        }
+    }
+}
+
+/// The most geodes every blueprint can open. The blueprints are searched in parallel.
+fn max_geodes(blueprints: &[Blueprint], minutes: usize) -> Vec<usize> {
+    let geodes = parallel::map(blueprints, |blueprint| Search::run(blueprint, minutes));
+    for (i, geodes) in geodes.iter().enumerate() {
+        debug!("Blueprint {}: {} geodes", i + 1, geodes);
+    }
+    geodes
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    type Input = Vec<Blueprint>;
+    type Config = Config;
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Blueprint::parse_all(file)
+    }
+
+    fn part_1(blueprints: &Self::Input, config: &Config) -> Answer {
+        max_geodes(blueprints, config.minutes_1)
+            .iter()
+            .enumerate()
+            .map(|(i, geodes)| (i + 1) * geodes)
+            .sum::<usize>()
+            .into()
+    }
+
+    fn part_2(blueprints: &Self::Input, config: &Config) -> Answer {
+        let blueprints = &blueprints[..config.blueprints_2.min(blueprints.len())];
+        max_geodes(blueprints, config.minutes_2)
+            .iter()
+            .product::<usize>()
+            .into()
+    }
+
+    /// `blueprints` blueprints with costs in the ranges of the puzzle.
+    fn generate(rng: &mut Rng, blueprints: usize) -> Option<String> {
+        Some(
+            (1..=blueprints)
+                .map(|id| {
+                    format!(
+                        "Blueprint {}: Each ore robot costs {} ore. Each clay robot costs {} ore. \
+                         Each obsidian robot costs {} ore and {} clay. \
+                         Each geode robot costs {} ore and {} obsidian.\n",
+                        id,
+                        rng.range(2..5),
+                        rng.range(2..5),
+                        rng.range(2..5),
+                        rng.range(5..21),
+                        rng.range(2..5),
+                        rng.range(7..21)
+                    )
+                })
+                .collect(),
+        )
+    }
 }
@@ -1,9 +1,13 @@
+use crate::error::{Error, Result};
+use crate::log::{debug, trace};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
 
 type Num = i64;
 
 #[derive(Clone)]
-struct EncryptedFile {
+pub struct EncryptedFile {
     items: Vec<Num>,
     positions: Vec<usize>,
 }
@@ -14,12 +18,12 @@ impl EncryptedFile {
         Self { items, positions }
     }
 
-    fn parse(file: &str) -> Self {
-        let items: Vec<Num> = util::read_lines(file)
-            .iter()
-            .map(|s| s.parse().unwrap())
-            .collect();
-        Self::new(items)
+    fn parse(file: &str) -> Result<Self> {
+        let items: Vec<Num> = util::lines(file)?.parse(util::parse_token)?;
+        if !items.contains(&0) {
+            return Err(Error::new("Missing item 0"));
+        }
+        Ok(Self::new(items))
     }
 
     fn find_zero(&self) -> usize {
@@ -47,7 +51,7 @@ impl EncryptedFile {
         let len = self.items.len() as Num;
         let mut pos = start as Num + item;
         if pos >= len {
-            pos = pos % (len - 1);
+            pos %= len - 1;
         }
         if pos <= 0 {
             pos = (pos % (len - 1)) + (len - 1);
@@ -75,21 +79,18 @@ impl EncryptedFile {
         if item != 0 {
             let start = self.find_position(id);
             let end = self.end_position(start, item);
-            // println!("\nbefore: {:?}", self.items);
             if end > start {
-                // println!("Move left: item {} from {} to {}", item, start, end);
+                trace!("Move right: item {} from {} to {}", item, start, end);
                 self.move_right(id, start, end)
             } else if start > end {
-                // println!("Move right: item {} from {} to {}", item, start, end);
+                trace!("Move left: item {} from {} to {}", item, start, end);
                 self.move_left(id, start, end)
             }
         }
-        // println!("after: {:?}\n", self.items);
     }
 
     fn move_all(&mut self) {
         for id in 0..self.items.len() {
-            // println!("Move item {id}");
             self.move_item(id)
         }
     }
@@ -103,7 +104,7 @@ impl EncryptedFile {
         let mut res = 0 as Num;
         for add_pos in [1000, 2000, 3000] {
             let item = self.get_item(pos_0 + add_pos);
-            println!("Item at relative position {} is {}", add_pos, item);
+            debug!("Item at relative position {} is {}", add_pos, item);
             res += item;
         }
         res
@@ -116,58 +117,116 @@ impl EncryptedFile {
     }
 }
 
-fn part_1(mut f: EncryptedFile) {
-    f.move_all();
-    println!("Part 1: {}", f.get_coordinates());
-}
+pub struct Day20;
+
+impl Solution for Day20 {
+    type Input = EncryptedFile;
+    type Config = ();
 
-fn part_2(mut f: EncryptedFile) {
-    f.apply_decryption_key(811589153);
-    for _mix in 0..10 {
-        // println!("Mix {mix}");
+    fn parse(file: &str) -> Result<Self::Input> {
+        EncryptedFile::parse(file)
+    }
+
+    fn part_1(f: &Self::Input, _: &()) -> Answer {
+        let mut f = f.clone();
         f.move_all();
+        f.get_coordinates().into()
+    }
+
+    fn part_2(f: &Self::Input, _: &()) -> Answer {
+        let mut f = f.clone();
+        f.apply_decryption_key(811589153);
+        for mix in 0..10 {
+            debug!("Mix {}", mix + 1);
+            f.move_all();
+        }
+        f.get_coordinates().into()
     }
-    println!("Part 2: {}", f.get_coordinates());
-}
 
-pub fn run() {
-    // let f = EncryptedFile::parse("20-test");
-    let f = EncryptedFile::parse("20-input");
-    part_1(f.clone());
-    part_2(f.clone());
+    fn generate(rng: &mut Rng, len: usize) -> Option<String> {
+        let mut items: Vec<Num> = (1..len)
+            .map(|_| rng.range(1..10000) * if rng.chance(0.5) { -1 } else { 1 })
+            .collect();
+        items.insert(rng.index(items.len() + 1), 0);
+        Some(items.iter().map(|item| format!("{}\n", item)).collect())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn mixed(f: &EncryptedFile) -> Vec<Num> {
+        f.positions.iter().map(|id| f.items[*id]).collect()
+    }
+
     #[test]
     fn test_move_right() {
         let mut f = EncryptedFile::new(vec![1, 2, 3, 8]);
         f.move_item(0);
-        assert_eq!(f.positions, vec![0, 1, 2, 3]);
+        assert_eq!(f.positions, vec![1, 0, 2, 3]);
         f.move_item(2);
-        assert_eq!(f.items, vec![2, 1, 3, 8]);
+        assert_eq!(mixed(&f), vec![2, 1, 3, 8]);
         f.move_item(3);
-        assert_eq!(f.items, vec![2, 1, 8, 3]);
+        assert_eq!(mixed(&f), vec![2, 1, 8, 3]);
     }
 
     #[test]
     fn test_move_left() {
         let mut f = EncryptedFile::new(vec![0, -1, -2, -9]);
         f.move_item(0);
-        assert_eq!(f.items, vec![0, -1, -2, -9]);
+        assert_eq!(mixed(&f), vec![0, -1, -2, -9]);
         f.move_item(1);
-        assert_eq!(f.items, vec![0, -2, -9, -1]);
+        assert_eq!(mixed(&f), vec![0, -2, -9, -1]);
         f.move_item(2);
-        assert_eq!(f.items, vec![0, -9, -2, -1]);
+        assert_eq!(mixed(&f), vec![0, -9, -2, -1]);
         f.move_item(3);
-        assert_eq!(f.items, vec![0, -9, -2, -1]);
+        assert_eq!(mixed(&f), vec![0, -9, -2, -1]);
+    }
+
+    /// Mixes by removing every item and inserting it again, the way the puzzle describes it.
+    fn mixed_naively(items: &[Num]) -> Vec<Num> {
+        let mut list: Vec<usize> = (0..items.len()).collect();
+        let cycle = items.len() as Num - 1;
+        for (id, item) in items.iter().enumerate() {
+            let start = list.iter().position(|other| *other == id).unwrap();
+            list.remove(start);
+            let end = (start as Num + item).rem_euclid(cycle) as usize;
+            list.insert(end, id);
+        }
+        list.iter().map(|id| items[*id]).collect()
     }
 
-    // #[test]
-    // fn test_duplicates() {
-    //     let mut f =
-    //         EncryptedFile::new(vec![1 /*a*/, 2 /*a*/, 1 /*b*/, 2 /*b*/]);
-    // }
+    /// Rotates a circular list to start with its 0.
+    fn from_zero(items: &[Num]) -> Vec<Num> {
+        let zero = items.iter().position(|item| *item == 0).unwrap();
+        items[zero..]
+            .iter()
+            .chain(&items[..zero])
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn test_mixing() {
+        let mut rng = Rng::new(20);
+        for _ in 0..300 {
+            let len = rng.range(2..20) as usize;
+            let mut items: Vec<Num> = (1..len)
+                .map(|_| rng.range(1..50) * if rng.chance(0.5) { -1 } else { 1 })
+                .collect();
+            items.insert(rng.index(len), 0);
+            let mut f = EncryptedFile::new(items.clone());
+            f.move_all();
+            let mut positions = f.positions.clone();
+            positions.sort();
+            assert_eq!(positions, (0..len).collect::<Vec<_>>());
+            assert_eq!(
+                from_zero(&mixed(&f)),
+                from_zero(&mixed_naively(&items)),
+                "{:?}",
+                items
+            );
+        }
+    }
 }
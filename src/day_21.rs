@@ -9,18 +9,22 @@ enum Operator {
     Div,
 }
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use crate::error::{Error, Result};
+use crate::log::debug;
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
 
 impl Operator {
-    fn parse(s: &str) -> Self {
+    fn parse(s: &str) -> Result<Self> {
         match s {
-            "+" => Operator::Add,
-            "-" => Operator::Sub,
-            "*" => Operator::Mult,
-            "/" => Operator::Div,
-            _ => panic!("Illegal operator {}", s),
+            "+" => Ok(Operator::Add),
+            "-" => Ok(Operator::Sub),
+            "*" => Ok(Operator::Mult),
+            "/" => Ok(Operator::Div),
+            _ => Err(Error::invalid("Invalid operator", s)),
         }
     }
 
@@ -41,34 +45,44 @@ enum MonkeyJob {
 }
 
 #[derive(Clone)]
-struct Solver {
+pub struct Solver {
     id_map: HashMap<String, Id>,
     next_id: Id,
     jobs: HashMap<Id, MonkeyJob>,
 }
 
 impl Solver {
-    fn parse(file: &str) -> Self {
+    fn parse(file: &str) -> Result<Self> {
         let mut solver = Self {
             id_map: HashMap::new(),
             next_id: 0,
             jobs: HashMap::new(),
         };
-        for line in util::read_lines(file) {
+        util::lines(file)?.parse(|line| {
             let tokens: Vec<&str> = line.split_whitespace().collect();
-            let monkey = solver.lookup_monkey(&tokens[0].replace(":", ""));
-            let job = match tokens[1].parse() {
+            let name = util::token(&tokens, 0)?;
+            let name = name
+                .strip_suffix(':')
+                .ok_or_else(|| Error::invalid("Expected a monkey name ending in ':'", name))?;
+            let monkey = solver.lookup_monkey(name);
+            let job = match util::token(&tokens, 1)?.parse() {
                 Ok(num) => MonkeyJob::Number(num),
                 _ => {
                     let left = solver.lookup_monkey(tokens[1]);
-                    let op = Operator::parse(tokens[2]);
-                    let right = solver.lookup_monkey(tokens[3]);
+                    let op = Operator::parse(util::token(&tokens, 2)?)?;
+                    let right = solver.lookup_monkey(util::token(&tokens, 3)?);
                     MonkeyJob::Operation(left, op, right)
                 }
             };
             solver.jobs.insert(monkey, job);
+            Ok(())
+        })?;
+        for (name, id) in &solver.id_map {
+            if !solver.jobs.contains_key(id) {
+                return Err(Error::invalid("Monkey without a job", name));
+            }
         }
-        solver
+        Ok(solver)
     }
     fn lookup_monkey(&mut self, monkey: &str) -> Id {
         match self.id_map.get(monkey) {
@@ -112,43 +126,169 @@ impl Solver {
             _ => panic!("root has no operation????"),
         };
         *solver.jobs.get_mut(&humn).unwrap() = MonkeyJob::Number(value);
-        // println!("Root: {}\nhumn: {}\nJobs:\n{:#?}", root, humn, solver.jobs);
-        let res = solver.solve_for(root);
-        // println!("Jobs after:\n{:#?}", solver.jobs);
-        res
+        solver.solve_for(root)
     }
 }
 
-fn part_2(solver: &Solver) {
-    let mut start = 3000000000000u64; // > 0
-    let mut end = 4000000000000u64; // < 0
-    while start + 1 < end {
-        let mid = (start + end) / 2;
-        let res = solver.try_value(mid as f64);
-        println!("Try {} -> {}", mid, res);
-        if res > 0f64 {
-            start = mid
-        } else if res < 0f64 {
-            end = mid
+pub struct Day21;
+
+impl Solution for Day21 {
+    type Input = Solver;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Solver::parse(file)
+    }
+
+    fn part_1(solver: &Self::Input, _: &()) -> Answer {
+        (solver.solve_part_1() as i64).into()
+    }
+
+    fn part_2(solver: &Self::Input, _: &()) -> Answer {
+        // root's difference is monotonic in humn, so widen the upper bound until
+        // its sign flips and then bisect.
+        let mut start = 0u64;
+        let start_sign = solver.try_value(start as f64).signum();
+        let mut end = 1u64;
+        while solver.try_value(end as f64).signum() == start_sign {
+            start = end;
+            end *= 2;
+        }
+        while start + 1 < end {
+            let mid = (start + end) / 2;
+            let res = solver.try_value(mid as f64);
+            debug!("Try {} -> {}", mid, res);
+            if res == 0f64 {
+                return mid.into();
+            } else if res.signum() == start_sign {
+                start = mid
+            } else {
+                end = mid
+            }
+        }
+        if solver.try_value(start as f64) == 0f64 {
+            start.into()
         } else {
-            println!("Solved it! {}", mid);
-            return;
+            end.into()
         }
     }
-    println!(
-        "Didn't find it :(\nStart: {} -> {}, End: {} -> {}",
-        start,
-        solver.try_value(start as f64),
-        end,
-        solver.try_value(end as f64)
-    );
-}
 
-pub fn run() {
-    // let file = "21-test";
-    let file = "21-input";
-    let solver = Solver::parse(file);
-    println!("Part 1: {}", solver.solve_part_1());
-    println!("Part 2:");
-    part_2(&solver);
+    /// About `monkeys` monkeys. humn is behind a chain of operations below root, each with a
+    /// monkey yielding a constant on its other side, and the other side of root yields what
+    /// the chain gives for the answer of part 2. Divisions leave no remainder, neither for
+    /// that answer nor for humn's own number, and divide only what the chain multiplied
+    /// before, so every step of humn changes root by at least one. Constants are spread
+    /// over the remaining monkeys.
+    fn generate(rng: &mut Rng, monkeys: usize) -> Option<String> {
+        fn fresh(rng: &mut Rng, names: &mut HashSet<String>) -> String {
+            loop {
+                let name: String = (0..4)
+                    .map(|_| char::from(b'a' + rng.index(26) as u8))
+                    .collect();
+                if names.insert(name.clone()) {
+                    return name;
+                }
+            }
+        }
+        // A monkey yielding `value`, split into about `monkeys` monkeys with positive
+        // numbers.
+        fn constant(
+            rng: &mut Rng,
+            names: &mut HashSet<String>,
+            lines: &mut Vec<String>,
+            value: i64,
+            monkeys: usize,
+        ) -> String {
+            let name = fresh(rng, names);
+            if monkeys < 3 && value > 0 {
+                lines.push(format!("{}: {}", name, value));
+                return name;
+            }
+            let (left, op, right) = match rng.index(3) {
+                0 if value > 1 => {
+                    let left = rng.range(1..value);
+                    (left, '+', value - left)
+                }
+                1 if value > 0 && value < 1_000_000_000 => {
+                    let right = rng.range(2..10);
+                    (value * right, '/', right)
+                }
+                _ => {
+                    let right = rng.range(1..100) + (1 - value).max(0);
+                    (value + right, '-', right)
+                }
+            };
+            let left_monkeys = rng.index(monkeys.max(2) - 1);
+            let left = constant(rng, names, lines, left, left_monkeys);
+            let right = constant(
+                rng,
+                names,
+                lines,
+                right,
+                monkeys.saturating_sub(left_monkeys + 1),
+            );
+            lines.push(format!("{}: {} {} {}", name, left, op, right));
+            name
+        }
+
+        let mut names = HashSet::from(["root".to_string(), "humn".to_string()]);
+        let mut lines = Vec::new();
+        let steps = (monkeys / 8).max(1);
+        let constant_monkeys = monkeys.saturating_sub(2 + steps) / (steps + 1);
+        let own = rng.range(1..1000);
+        lines.push(format!("humn: {}", own));
+        // What the chain yields for the answer and for humn's own number, and how much
+        // more it yields for every step of humn.
+        let mut values = [rng.range(1..1_000_000), own];
+        let mut scale = 1;
+        let mut chain = "humn".to_string();
+        for _ in 0..steps {
+            let fits = |factor: i64| {
+                values
+                    .iter()
+                    .all(|value| value.abs() * factor < 1_000_000_000_000)
+            };
+            let (op, other) = match rng.index(4) {
+                0 => ('+', rng.range(1..1000)),
+                1 => ('-', rng.range(1..1000)),
+                2 => match rng.range(2..10) {
+                    factor if fits(factor) => ('*', factor),
+                    _ => ('+', rng.range(1..1000)),
+                },
+                _ => match (2..10).find(|divisor| {
+                    scale % divisor == 0 && values.iter().all(|value| value % divisor == 0)
+                }) {
+                    Some(divisor) => ('/', divisor),
+                    None => ('-', rng.range(1..1000)),
+                },
+            };
+            for value in &mut values {
+                *value = match op {
+                    '+' => *value + other,
+                    '-' => *value - other,
+                    '*' => *value * other,
+                    _ => *value / other,
+                };
+            }
+            match op {
+                '*' => scale *= other,
+                '/' => scale /= other,
+                _ => (),
+            }
+            let other = constant(rng, &mut names, &mut lines, other, constant_monkeys);
+            let name = fresh(rng, &mut names);
+            // Either side of a sum or product.
+            let line = if matches!(op, '+' | '*') && rng.chance(0.5) {
+                format!("{}: {} {} {}", name, other, op, chain)
+            } else {
+                format!("{}: {} {} {}", name, chain, op, other)
+            };
+            lines.push(line);
+            chain = name;
+        }
+        let other = constant(rng, &mut names, &mut lines, values[0], constant_monkeys);
+        lines.push(format!("root: {} + {}", chain, other));
+        rng.shuffle(&mut lines);
+        Some(lines.iter().map(|line| format!("{}\n", line)).collect())
+    }
 }
@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 
+use crate::error::{Error, Result};
+use crate::log::trace;
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
+use crate::util::Grid;
 
 #[derive(Debug, Clone)]
 enum Tile {
@@ -10,12 +15,12 @@ enum Tile {
 }
 
 impl Tile {
-    fn parse(c: char) -> Self {
+    fn parse(c: char) -> Result<Self> {
         match c {
-            ' ' => Tile::Wrap,
-            '.' => Tile::Empty,
-            '#' => Tile::Block,
-            _ => panic!("Illegal tile char: {}", c),
+            ' ' => Ok(Tile::Wrap),
+            '.' => Ok(Tile::Empty),
+            '#' => Ok(Tile::Block),
+            _ => Err(Error::invalid("Invalid tile", c)),
         }
     }
 
@@ -26,14 +31,14 @@ impl Tile {
 
 #[derive(Clone, Debug)]
 enum Step {
-    Forward,
+    Forward(usize),
     Left,
     Right,
 }
 
 #[derive(Clone)]
 struct Map {
-    tiles: Vec<Vec<Tile>>,
+    tiles: Grid<Tile>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -72,7 +77,7 @@ impl Direction {
         }
     }
 
-    fn to_facing(&self) -> usize {
+    fn to_facing(self) -> usize {
         match self {
             Direction::Right => 0,
             Direction::Down => 1,
@@ -83,39 +88,40 @@ impl Direction {
 }
 
 impl Map {
-    fn parse(block: &[String]) -> Self {
-        let tiles: Vec<Vec<Tile>> = block
-            .iter()
-            .map(|line| line.chars().map(Tile::parse).collect())
-            .collect();
-        Self { tiles }
+    fn parse(block: &[String]) -> Result<Self> {
+        let tiles = Grid::parse_padded(block, Tile::Wrap, Tile::parse)?;
+        let map = Self { tiles };
+        if !map.tiles.row(0).iter().any(Tile::on_map) {
+            return Err(Error::new("No open tile in the first row").at_line(1));
+        }
+        Ok(map)
     }
 
     fn contains(&self, row: usize, col: usize) -> bool {
-        row < self.tiles.len() && col < self.tiles[row].len() && self.tiles[row][col].on_map()
+        self.tiles.get((col, row)).is_some_and(Tile::on_map)
     }
 
     fn first_of_row(&self, row: usize) -> usize {
-        (0..self.tiles[row].len())
+        (0..self.tiles.width())
             .find(|col| self.contains(row, *col))
             .expect("First of row")
     }
 
     fn last_of_row(&self, row: usize) -> usize {
-        (0..self.tiles[row].len())
+        (0..self.tiles.width())
             .rev()
             .find(|col| self.contains(row, *col))
             .expect("Last of row")
     }
 
     fn first_of_col(&self, col: usize) -> usize {
-        (0..self.tiles.len())
+        (0..self.tiles.height())
             .find(|row| self.contains(*row, col))
             .expect("First of col")
     }
 
     fn last_of_col(&self, col: usize) -> usize {
-        (0..self.tiles.len())
+        (0..self.tiles.height())
             .rev()
             .find(|row| self.contains(*row, col))
             .expect("Last of col")
@@ -125,12 +131,7 @@ impl Map {
         if row > 0 && self.contains(row - 1, col) {
             (row - 1, col)
         } else {
-            // println!(
-            //     "Wrap up of {} -> {} - tile: {:?}",
-            //     col,
-            //     self.last_of_col(col),
-            //     self.tiles[row][self.last_of_col(col)]
-            // );
+            trace!("Wrap up of column {} to row {}", col, self.last_of_col(col));
             (self.last_of_col(col), col)
         }
     }
@@ -200,9 +201,8 @@ fn right(row: usize, col: usize) -> Position {
 }
 
 fn link(map: &mut HashMap<Position, Position>, from: Position, to: Position) {
-    match map.insert(from, to) {
-        Some(_) => panic!("Duplicate cube link source"),
-        None => {}
+    if map.insert(from, to).is_some() {
+        panic!("Duplicate cube link source")
     }
     let back_from = Position {
         direction: to.direction.opposite(),
@@ -212,98 +212,152 @@ fn link(map: &mut HashMap<Position, Position>, from: Position, to: Position) {
         direction: from.direction.opposite(),
         ..from
     };
-    match map.insert(back_from, back_to) {
-        Some(_) => panic!("Duplicate back link source"),
-        None => {}
+    if map.insert(back_from, back_to).is_some() {
+        panic!("Duplicate back link source")
     }
 }
 
-fn calc_cube_links(file: &str) -> HashMap<Position, Position> {
+/// How the map folds into a cube. Only the nets of the official test and puzzle inputs
+/// are known, but their faces can have any size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CubeLayout {
+    Test,
+    Input,
+}
+
+impl CubeLayout {
+    /// The faces of the net as row and column, in units of the side length.
+    fn faces(self) -> [(usize, usize); 6] {
+        match self {
+            CubeLayout::Test => [(0, 2), (1, 0), (1, 1), (1, 2), (2, 2), (2, 3)],
+            CubeLayout::Input => [(0, 1), (0, 2), (1, 1), (2, 0), (2, 1), (3, 0)],
+        }
+    }
+
+    /// The layout the map folds like, with the side length of its faces.
+    fn detect(map: &Map) -> Option<(Self, usize)> {
+        let tiles = map
+            .tiles
+            .rows()
+            .flat_map(|row| row.iter())
+            .filter(|tile| tile.on_map())
+            .count();
+        let side = (1..).take_while(|side| 6 * side * side <= tiles).last()?;
+        if 6 * side * side != tiles {
+            return None;
+        }
+        let mut faces = Vec::new();
+        for row in 0..map.tiles.height() / side {
+            for col in 0..map.tiles.width() / side {
+                if map.contains(row * side, col * side) {
+                    faces.push((row, col));
+                }
+            }
+        }
+        [CubeLayout::Test, CubeLayout::Input]
+            .into_iter()
+            .find(|layout| faces == layout.faces())
+            .map(|layout| (layout, side))
+    }
+}
+
+fn calc_cube_links(layout: CubeLayout, c: usize) -> HashMap<Position, Position> {
     let mut ret = HashMap::new();
-    if file == "22-test" {
-        let c = 4;
-        for p in 0..c {
-            let n = c - 1 - p;
-            link(&mut ret, up(0, 2 * c + p), down(c, n));
-            link(&mut ret, left(p, 2 * c), up(c, c + p));
-            link(&mut ret, left(c + p, 0), up(3 * c - 1, 3 * c + n));
-            link(&mut ret, down(2 * c - 1, p), up(3 * c - 1, 2 * c + n));
-            link(&mut ret, down(2 * c - 1, c + p), right(2 * c + n, 2 * c));
-            link(&mut ret, right(p, 3 * c - 1), left(2 * c + n, 4 * c - 1));
-            link(&mut ret, right(c + p, 3 * c - 1), down(2 * c, 3 * c + n));
+    match layout {
+        CubeLayout::Test => {
+            for p in 0..c {
+                let n = c - 1 - p;
+                link(&mut ret, up(0, 2 * c + p), down(c, n));
+                link(&mut ret, left(p, 2 * c), up(c, c + p));
+                link(&mut ret, left(c + p, 0), up(3 * c - 1, 3 * c + n));
+                link(&mut ret, down(2 * c - 1, p), up(3 * c - 1, 2 * c + n));
+                link(&mut ret, down(2 * c - 1, c + p), right(2 * c + n, 2 * c));
+                link(&mut ret, right(p, 3 * c - 1), left(2 * c + n, 4 * c - 1));
+                link(&mut ret, right(c + p, 3 * c - 1), down(2 * c, 3 * c + n));
+            }
         }
-    } else if file == "22-input" {
-        let c = 50;
-        for p in 0..c {
-            let n = c - 1 - p;
-            link(&mut ret, up(0, c + p), right(3 * c + p, 0));
-            link(&mut ret, up(2 * c, p), right(c + p, c));
-            link(&mut ret, left(p, c), right(2 * c + n, 0));
-            link(&mut ret, right(3 * c + p, c - 1), up(3 * c - 1, c + p));
-            link(&mut ret, up(0, 2 * c + p), up(4 * c - 1, p));
-            link(&mut ret, right(p, 3 * c - 1), left(2 * c + n, 2 * c - 1));
-            link(&mut ret, right(c + p, 2 * c - 1), up(c - 1, 2 * c + p));
+        CubeLayout::Input => {
+            for p in 0..c {
+                let n = c - 1 - p;
+                link(&mut ret, up(0, c + p), right(3 * c + p, 0));
+                link(&mut ret, up(2 * c, p), right(c + p, c));
+                link(&mut ret, left(p, c), right(2 * c + n, 0));
+                link(&mut ret, right(3 * c + p, c - 1), up(3 * c - 1, c + p));
+                link(&mut ret, up(0, 2 * c + p), up(4 * c - 1, p));
+                link(&mut ret, right(p, 3 * c - 1), left(2 * c + n, 2 * c - 1));
+                link(&mut ret, right(c + p, 2 * c - 1), up(c - 1, 2 * c + p));
+            }
         }
-    } else {
-        panic!("Illegal file")
     }
     ret
 }
 
+fn parse_path(line: &str) -> Result<Vec<Step>> {
+    let mut path = Vec::new();
+    let mut number_str = "".to_string();
+    let push_forward = |number_str: &str, path: &mut Vec<Step>| -> Result<()> {
+        path.push(Step::Forward(util::parse_token(number_str)?));
+        Ok(())
+    };
+    for (i, c) in line.chars().enumerate() {
+        match c {
+            'R' | 'L' => {
+                if number_str.is_empty() {
+                    return Err(Error::invalid("Turn without a step count", c).at_column(i + 1));
+                }
+                push_forward(&number_str, &mut path)?;
+                number_str.clear();
+                path.push(match c {
+                    'R' => Step::Right,
+                    _ => Step::Left,
+                })
+            }
+            '0'..='9' => number_str.push(c),
+            _ => return Err(Error::invalid("Invalid step", c).at_column(i + 1)),
+        }
+    }
+    if !number_str.is_empty() {
+        push_forward(&number_str, &mut path)?;
+    }
+    Ok(path)
+}
+
 #[derive(Clone)]
-struct Solver {
+pub struct Solver {
     map: Map,
     position: Position,
     path: Vec<Step>,
+    /// The layout the map folds like and the side length of its faces, if it's known. Only
+    /// part 2 needs it.
+    cube: Option<(CubeLayout, usize)>,
     cube_links: HashMap<Position, Position>,
 }
 
 impl Solver {
-    fn parse(file: &str) -> Self {
-        let blocks = util::read_blocks(file);
-        let map = Map::parse(&blocks[0]);
+    fn parse(file: &str) -> Result<Self> {
+        let blocks = util::read_blocks(file)?;
+        let map = util::block(&blocks, 0)?.parse(Map::parse)?;
+        let cube = CubeLayout::detect(&map);
         let position = Position {
             row: 0,
             col: map.first_of_row(0),
             direction: Direction::Right,
         };
-        let mut path = Vec::new();
-        let mut number_str = "".to_string();
-        for c in blocks[1][0].chars() {
-            match c {
-                'R' | 'L' => {
-                    if !number_str.is_empty() {
-                        for _ in 0..number_str.parse().unwrap() {
-                            path.push(Step::Forward);
-                        }
-                        number_str.clear();
-                        path.push(match c {
-                            'R' => Step::Right,
-                            'L' => Step::Left,
-                            _ => panic!(),
-                        })
-                    }
-                }
-                _ => number_str.push(c),
-            }
-        }
-        if !number_str.is_empty() {
-            for _ in 0..number_str.parse().unwrap() {
-                path.push(Step::Forward);
-            }
-        }
-        let cube_links = calc_cube_links(file);
-        Self {
+        let path = util::block(&blocks, 1)?.parse(|lines| {
+            util::parse_lines(&lines[..1], parse_path).map(|mut paths| paths.remove(0))
+        })?;
+        Ok(Self {
             map,
             position,
             path,
-            cube_links,
-        }
+            cube,
+            cube_links: HashMap::new(),
+        })
     }
 
-    #[allow(dead_code)]
-    fn print_cube_links(&self) {
-        let c = 50;
+    /// Marks both ends of the rightward links out of the bottom face of the input layout with
+    /// the same letter.
+    fn render_cube_links(&self, c: usize) -> String {
         let dir = Direction::Right;
         let min_row = 3 * c;
         let max_row = min_row + c;
@@ -320,72 +374,73 @@ impl Solver {
                 }
             }
         }
-        for (y, row) in self.map.tiles.iter().enumerate() {
+        let mut s = String::new();
+        for (y, row) in self.map.tiles.rows().enumerate() {
             for (x, tile) in row.iter().enumerate() {
                 if let Some(letter) = link_map.get(&(y, x)) {
-                    print!("{}", *letter as char);
+                    s.push(*letter as char);
                 } else if matches!(tile, Tile::Wrap) {
-                    print!(" ")
+                    s.push(' ')
                 } else {
-                    print!(".")
+                    s.push('.')
                 }
             }
-            println!()
+            s.push('\n')
+        }
+        s
+    }
+
+    /// Moves one tile forward, returning whether a block was in the way.
+    fn go_forward(&mut self, on_cube: bool) -> bool {
+        if on_cube {
+            if let Some(position) = self.cube_links.get(&self.position) {
+                if matches!(self.map.tiles[(position.col, position.row)], Tile::Block) {
+                    return false;
+                }
+                self.position = *position;
+                return true;
+            }
+        }
+        let mut row = self.position.row;
+        let mut col = self.position.col;
+        match self.position.direction {
+            Direction::Up => (row, col) = self.map.up_of(row, col),
+            Direction::Down => (row, col) = self.map.down_of(row, col),
+            Direction::Left => (row, col) = self.map.left_of(row, col),
+            Direction::Right => (row, col) = self.map.right_of(row, col),
+        }
+        match self.map.tiles[(col, row)] {
+            Tile::Empty => {
+                self.position.row = row;
+                self.position.col = col;
+                true
+            }
+            Tile::Block => false,
+            Tile::Wrap => panic!("Shouldn't land on wrap"),
         }
     }
 
     fn go_step(&mut self, step: &Step, on_cube: bool) {
-        // println!("Step {:?} from {:#?} ", step, self.position);
         match step {
             Step::Left => self.position.direction.rotate_left(),
             Step::Right => self.position.direction.rotate_right(),
-            Step::Forward => {
-                if on_cube {
-                    if let Some(position) = self.cube_links.get(&self.position) {
-                        if !matches!(self.map.tiles[position.row][position.col], Tile::Block) {
-                            self.position = *position;
-                        }
-                        return;
-                    }
-                }
-                let mut row = self.position.row;
-                let mut col = self.position.col;
-                match self.position.direction {
-                    Direction::Up => (row, col) = self.map.up_of(row, col),
-                    Direction::Down => (row, col) = self.map.down_of(row, col),
-                    Direction::Left => (row, col) = self.map.left_of(row, col),
-                    Direction::Right => (row, col) = self.map.right_of(row, col),
-                }
-                match self.map.tiles[row][col] {
-                    Tile::Empty => {
-                        self.position.row = row;
-                        self.position.col = col;
+            Step::Forward(tiles) => {
+                for _ in 0..*tiles {
+                    if !self.go_forward(on_cube) {
+                        break;
                     }
-                    Tile::Block => (),
-                    Tile::Wrap => panic!("Shouldn't land on wrap"),
                 }
             }
         }
     }
 
     fn run(&mut self, on_cube: bool) {
-        let mut fw = 0;
-        for (i, step) in self.path.clone().iter().enumerate() {
-            if i < 10000 {
-                if let Step::Forward = step {
-                    fw += 1;
-                } else {
-                    if fw > 0 {
-                        println!("Forward {}", fw);
-                        fw = 0;
-                    }
-                    self.print_state();
-                    println!("\nStep {:?}", step);
-                }
+        for step in self.path.clone().iter() {
+            match step {
+                Step::Forward(tiles) => trace!("Forward {}", tiles),
+                _ => trace!("Before {:?}:\n{}", step, self.render_state()),
             }
-
             self.go_step(step, on_cube);
-            // println!("{:?}", self.position);
         }
     }
 
@@ -393,9 +448,16 @@ impl Solver {
         (self.position.row + 1, self.position.col + 1)
     }
 
-    fn print_state(&self) {
-        println!();
-        for (y, row) in self.map.tiles.iter().enumerate() {
+    fn password(&self) -> usize {
+        let facing = self.position.direction.to_facing();
+        let (row, col) = self.solution_pos();
+        1000 * row + 4 * col + facing
+    }
+
+    /// Draws the map with the current position and direction.
+    fn render_state(&self) -> String {
+        let mut s = String::new();
+        for (y, row) in self.map.tiles.rows().enumerate() {
             for (x, tile) in row.iter().enumerate() {
                 let c = if self.position.row == y && self.position.col == x {
                     match self.position.direction {
@@ -411,34 +473,94 @@ impl Solver {
                         Tile::Wrap => ' ',
                     }
                 };
-                print!("{}", c);
+                s.push(c);
             }
-            println!()
+            s.push('\n')
         }
+        s
     }
 }
 
-#[allow(dead_code)]
-fn part_1(mut solver: Solver) {
-    solver.run(false);
-    let facing = solver.position.direction.to_facing();
-    let (row, col) = solver.solution_pos();
-    let res = 1000 * row + 4 * col + facing;
-    println!("Part 1: {}", res);
-}
+pub struct Day22;
+
+impl Solution for Day22 {
+    type Input = Solver;
+    type Config = ();
 
-fn part_2(mut solver: Solver) {
-    solver.run(true);
-    let facing = solver.position.direction.to_facing();
-    let (row, col) = solver.solution_pos();
-    let res = 1000 * row + 4 * col + facing;
-    println!("Part 2: {}", res);
+    fn parse(file: &str) -> Result<Self::Input> {
+        Solver::parse(file)
+    }
+
+    fn part_1(solver: &Self::Input, _: &()) -> Answer {
+        let mut solver = solver.clone();
+        solver.run(false);
+        solver.password().into()
+    }
+
+    fn part_2(solver: &Self::Input, _: &()) -> Answer {
+        let mut solver = solver.clone();
+        let (layout, side) = solver
+            .cube
+            .expect("The map doesn't fold like the example or the puzzle input");
+        solver.cube_links = calc_cube_links(layout, side);
+        trace!("Cube links:\n{}", solver.render_cube_links(side));
+        solver.run(true);
+        solver.password().into()
+    }
+
+    /// A map folding like the puzzle input, with faces a twentieth of `moves` wide but at
+    /// least two tiles, a tenth of them walls, and a path of `moves` moves and turns.
+    fn generate(rng: &mut Rng, moves: usize) -> Option<String> {
+        let side = (moves / 20).max(2);
+        let faces = CubeLayout::Input.faces();
+        let mut res = String::new();
+        for row in 0..4 * side {
+            let mut line = String::new();
+            for col in 0..3 * side {
+                line.push(if !faces.contains(&(row / side, col / side)) {
+                    ' '
+                } else if (row, col) != (0, side) && rng.chance(0.1) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            res.push_str(line.trim_end());
+            res.push('\n');
+        }
+        res.push('\n');
+        for i in 0..moves.max(1) {
+            if i > 0 {
+                res.push(*rng.choose(&['L', 'R']));
+            }
+            res.push_str(&rng.range(1..2 * side as i64).to_string());
+        }
+        res.push('\n');
+        Some(res)
+    }
 }
 
-pub fn run() {
-    // let solver = Solver::parse("22-test");
-    let solver = Solver::parse("22-input");
-    // solver.print_cube_links();
-    // part_1(solver.clone());
-    part_2(solver.clone());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_layout() {
+        let solver = Solver::parse("22-test").unwrap();
+        assert_eq!(solver.cube, Some((CubeLayout::Test, 4)));
+        let solver = Solver::parse("22-input").unwrap();
+        assert_eq!(solver.cube, Some((CubeLayout::Input, 50)));
+    }
+
+    #[test]
+    fn test_parse_path_rejects_bare_turns() {
+        assert_eq!(
+            parse_path("R10").unwrap_err(),
+            Error::invalid("Turn without a step count", 'R').at_column(1)
+        );
+        assert_eq!(
+            parse_path("10RL5").unwrap_err(),
+            Error::invalid("Turn without a step count", 'L').at_column(4)
+        );
+    }
 }
@@ -1,27 +1,32 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fmt::Display,
-};
+use std::{collections::HashMap, fmt::Display};
 
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::geom::{Bounds, Vec2};
+use crate::util::rng::Rng;
+use crate::util::Grid;
 
 type Num = i32;
-type Point = (Num, Num);
+type Point = Vec2<Num>;
 
-struct State {
+#[derive(Clone)]
+pub struct State {
     elves: Vec<Point>,
-    occupied: HashSet<Point>,
+    /// Covers every elf with a margin, shifted so that `origin` is at its top left.
+    occupied: Grid<bool>,
+    origin: Point,
     consider_offset: usize,
 }
 
-const N: Point = (0, -1);
-const NW: Point = (-1, -1);
-const NE: Point = (1, -1);
-const S: Point = (0, 1);
-const SW: Point = (-1, 1);
-const SE: Point = (1, 1);
-const W: Point = (-1, 0);
-const E: Point = (1, 0);
+const N: Point = Vec2 { x: 0, y: -1 };
+const NW: Point = Vec2 { x: -1, y: -1 };
+const NE: Point = Vec2 { x: 1, y: -1 };
+const S: Point = Vec2 { x: 0, y: 1 };
+const SW: Point = Vec2 { x: -1, y: 1 };
+const SE: Point = Vec2 { x: 1, y: 1 };
+const W: Point = Vec2 { x: -1, y: 0 };
+const E: Point = Vec2 { x: 1, y: 0 };
 
 const ALL_DIRECTIONS: [Point; 8] = [N, NW, NE, S, SE, SW, E, W];
 
@@ -33,42 +38,81 @@ enum ProposalStatus {
 }
 
 impl State {
-    fn parse(file: &str) -> Self {
+    fn parse(file: &str) -> Result<Self> {
         let mut elves = vec![];
-        let mut occupied = HashSet::new();
-        for (y, line) in util::read_lines(file).iter().enumerate() {
+        let mut y = 0;
+        util::lines(file)?.parse(|line| {
             for (x, c) in line.chars().enumerate() {
-                if c == '#' {
-                    elves.push((x as Num, y as Num));
-                    occupied.insert((x as Num, y as Num));
+                match c {
+                    '#' => elves.push(Vec2::new(x as Num, y as Num)),
+                    '.' => (),
+                    _ => return Err(Error::invalid("Invalid tile", c).at_column(x + 1)),
                 }
             }
+            y += 1;
+            Ok(())
+        })?;
+        if elves.is_empty() {
+            return Err(Error::new("No elves"));
         }
-        Self {
+        let mut state = Self {
             elves,
-            occupied,
+            occupied: Grid::new(0, 0, false),
+            origin: Vec2::zero(),
             consider_offset: 0,
-        }
+        };
+        state.ensure_margin();
+        Ok(state)
+    }
+
+    fn is_occupied(&self, point: &Point) -> bool {
+        let Vec2 { x, y } = *point - self.origin;
+        self.occupied
+            .to_pos(x as isize, y as isize)
+            .is_some_and(|pos| self.occupied[pos])
+    }
+
+    fn set_occupied(&mut self, point: &Point, occupied: bool) {
+        let Vec2 { x, y } = *point - self.origin;
+        self.occupied[(x as usize, y as usize)] = occupied;
     }
 
-    fn neighbour(point: &Point, delta: &Point) -> Point {
-        (point.0 + delta.0, point.1 + delta.1)
+    /// Makes room for every elf to move, rebuilding the grid with some margin once an elf
+    /// reaches its edge.
+    fn ensure_margin(&mut self) {
+        let elves = self.bounding_box();
+        let size = Vec2::new(self.occupied.width() as Num, self.occupied.height() as Num);
+        let grid = Bounds {
+            min: self.origin,
+            max: self.origin + size - Vec2::new(1, 1),
+        };
+        let needed = elves.grow(1);
+        if grid.contains(needed.min) && grid.contains(needed.max) {
+            return;
+        }
+        let room = elves.grow(16);
+        let size = room.size();
+        self.origin = room.min;
+        self.occupied = Grid::new(size.x as usize, size.y as usize, false);
+        for elf in self.elves.clone() {
+            self.set_occupied(&elf, true);
+        }
     }
 
     fn consider_direction(&self, point: &Point, check_deltas: &[Point; 3]) -> Option<Point> {
         for delta in check_deltas {
-            let neighb = Self::neighbour(point, delta);
-            if self.occupied.contains(&neighb) {
+            let neighb = *point + *delta;
+            if self.is_occupied(&neighb) {
                 return None;
             }
         }
-        return Some(Self::neighbour(point, &check_deltas[0]));
+        Some(*point + check_deltas[0])
     }
 
     fn does_consider(&self, point: &Point) -> bool {
         ALL_DIRECTIONS
             .iter()
-            .find(|delta| self.occupied.contains(&Self::neighbour(point, delta)))
+            .find(|delta| self.is_occupied(&(*point + **delta)))
             .is_some()
     }
 
@@ -84,12 +128,14 @@ impl State {
     }
 
     fn move_elf(&mut self, id: usize, point: &Point) {
-        self.occupied.remove(&self.elves[id]);
-        self.occupied.insert(*point);
+        let from = self.elves[id];
+        self.set_occupied(&from, false);
+        self.set_occupied(point, true);
         self.elves[id] = *point;
     }
 
     fn make_turn(&mut self) -> bool {
+        self.ensure_margin();
         let mut proposals = HashMap::new();
         for (id, point) in self.elves.iter().enumerate() {
             if self.does_consider(point) {
@@ -114,27 +160,17 @@ impl State {
         moved_any
     }
 
-    fn bounding_box(&self) -> (Point, Point) {
-        let mut min_x = 99999999;
-        let mut min_y = 99999999;
-        let mut max_x = -99999999;
-        let mut max_y = -99999999;
-        for (x, y) in &self.elves {
-            min_x = min_x.min(*x);
-            min_y = min_y.min(*y);
-            max_x = max_x.max(*x);
-            max_y = max_y.max(*y);
-        }
-        ((min_x, min_y), (max_x, max_y))
+    fn bounding_box(&self) -> Bounds<Point> {
+        Bounds::of(self.elves.iter().copied()).expect("No elves")
     }
 }
 
 impl Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let ((min_x, min_y), (max_x, max_y)) = self.bounding_box();
-        for y in min_y..=max_y {
-            for x in min_x..=max_x {
-                if self.occupied.contains(&(x, y)) {
+        let Bounds { min, max } = self.bounding_box();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                if self.is_occupied(&Vec2::new(x, y)) {
                     write!(f, "#")?;
                 } else {
                     write!(f, ".")?;
@@ -146,27 +182,49 @@ impl Display for State {
     }
 }
 
-fn part_1(file: &str) -> usize {
-    let mut state = State::parse(file);
-    for _ in 0..10 {
-        state.make_turn();
+pub struct Day23;
+
+impl Solution for Day23 {
+    type Input = State;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        State::parse(file)
     }
-    let ((min_x, min_y), (max_x, max_y)) = state.bounding_box();
-    let points = (max_x + 1 - min_x) * (max_y + 1 - min_y);
-    points as usize - state.elves.len()
-}
 
-fn part_2(file: &str) -> usize {
-    let mut state = State::parse(file);
-    for i in 1.. {
-        if !state.make_turn() {
-            return i;
+    fn part_1(state: &Self::Input, _: &()) -> Answer {
+        let mut state = state.clone();
+        for _ in 0..10 {
+            state.make_turn();
         }
+        let size = state.bounding_box().size();
+        let points = size.x * size.y;
+        (points as usize - state.elves.len()).into()
     }
-    return 0;
-}
 
-pub fn run() {
-    println!("Part 1: {}", part_1("23-input"));
-    println!("Part 2: {}", part_2("23-input"))
+    fn part_2(state: &Self::Input, _: &()) -> Answer {
+        let mut state = state.clone();
+        let mut i = 1usize;
+        while state.make_turn() {
+            i += 1;
+        }
+        i.into()
+    }
+
+    /// `elves` elves on about half of the tiles of a square, as dense as in the puzzle. The
+    /// rounds until they have spread out grow with the square, so the input grows with the
+    /// number of elves rather than the side.
+    fn generate(rng: &mut Rng, elves: usize) -> Option<String> {
+        let elves = elves.max(1);
+        let side = ((2 * elves) as f64).sqrt().ceil() as usize;
+        let mut tiles = vec!['.'; side * side];
+        tiles[..elves].fill('#');
+        rng.shuffle(&mut tiles);
+        Some(
+            tiles
+                .chunks(side)
+                .map(|row| row.iter().chain(['\n'].iter()).collect::<String>())
+                .collect(),
+        )
+    }
 }
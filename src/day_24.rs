@@ -1,9 +1,18 @@
-use std::collections::HashSet;
-
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::grid::{Grid, Pos};
+use crate::util::rng::Rng;
+use crate::util::search;
 
-type Point = (usize, usize);
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Open,
+    Blizzard(Direction),
+}
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum Direction {
     Down,
     Right,
@@ -11,187 +20,188 @@ enum Direction {
     Up,
 }
 
-struct Blizzard {
-    position: Point,
-    direction: Direction,
+impl Tile {
+    fn parse(c: char) -> Result<Self> {
+        Ok(match c {
+            '#' => Tile::Wall,
+            '.' => Tile::Open,
+            '^' => Tile::Blizzard(Direction::Up),
+            '<' => Tile::Blizzard(Direction::Left),
+            '>' => Tile::Blizzard(Direction::Right),
+            'v' => Tile::Blizzard(Direction::Down),
+            _ => return Err(Error::invalid("Invalid tile", c)),
+        })
+    }
 }
 
-struct Solver {
-    width: usize,
-    height: usize,
-    start: Point,
-    goal: Point,
-    blizzards: Vec<Blizzard>,
-    time: usize,
-    possible_positions: HashSet<Point>,
+/// A position at a point in time. The blizzards repeat after `period` minutes, so the time
+/// is only kept modulo that.
+type State = (Pos, usize);
+
+pub struct Valley {
+    tiles: Grid<Tile>,
+    start: Pos,
+    goal: Pos,
+    period: usize,
 }
 
-impl Solver {
-    fn calc_occupied(&mut self) -> HashSet<Point> {
-        self.blizzards.iter().map(|b| b.position).collect()
+impl Valley {
+    fn parse(file: &str) -> Result<Self> {
+        Self::from_lines(&util::read_lines(file)?)
     }
 
-    fn move_blizzards(&mut self) {
-        for blizzard in &mut self.blizzards {
-            match blizzard.direction {
-                Direction::Up => {
-                    blizzard.position.1 = if blizzard.position.1 > 1 {
-                        blizzard.position.1 - 1
-                    } else {
-                        self.height - 2
-                    }
-                }
-                Direction::Left => {
-                    blizzard.position.0 = if blizzard.position.0 > 1 {
-                        blizzard.position.0 - 1
-                    } else {
-                        self.width - 2
-                    }
-                }
-                Direction::Down => {
-                    blizzard.position.1 = if blizzard.position.1 + 2 < self.height {
-                        blizzard.position.1 + 1
-                    } else {
-                        1
-                    }
-                }
-                Direction::Right => {
-                    blizzard.position.0 = if blizzard.position.0 + 2 < self.width {
-                        blizzard.position.0 + 1
-                    } else {
-                        1
-                    }
-                }
-            }
+    fn from_lines<S: AsRef<str>>(lines: &[S]) -> Result<Self> {
+        let tiles = Grid::parse(lines, Tile::parse)?;
+        let width = tiles.width();
+        let height = tiles.height();
+        if height < 3 || width < 3 {
+            return Err(Error::new("Expected a valley of at least 3x3 tiles"));
         }
+        Ok(Self {
+            start: (1, 0),
+            goal: (width - 2, height - 1),
+            period: num::integer::lcm(width - 2, height - 2),
+            tiles,
+        })
     }
 
-    fn parse(file: &str) -> Self {
-        let lines = util::read_lines(file);
-        let width = lines[0].len();
-        let height = lines.len();
-        let start = (1, 0);
-        let goal = (width - 2, height - 1);
-        let mut blizzards = vec![];
-        for (y, line) in lines.iter().enumerate() {
-            for (x, c) in line.chars().enumerate() {
-                let pos = (x, y);
-                match c {
-                    '^' => blizzards.push(Blizzard {
-                        position: pos,
-                        direction: Direction::Up,
-                    }),
-                    '<' => blizzards.push(Blizzard {
-                        position: pos,
-                        direction: Direction::Left,
-                    }),
-                    '>' => blizzards.push(Blizzard {
-                        position: pos,
-                        direction: Direction::Right,
-                    }),
-                    'v' => blizzards.push(Blizzard {
-                        position: pos,
-                        direction: Direction::Down,
-                    }),
-                    _ => (),
-                }
-            }
+    /// Whether no wall or blizzard is at `pos` after `time` minutes. Blizzards wrap around
+    /// inside the walls, so it's enough to look back along the row and the column.
+    fn is_free(&self, (x, y): Pos, time: usize) -> bool {
+        if self.tiles[(x, y)] == Tile::Wall {
+            return false;
+        }
+        if y == 0 || y == self.tiles.height() - 1 {
+            return true;
         }
-        let mut possible_positions = HashSet::new();
-        possible_positions.insert(start);
-        let mut solver = Self {
-            width,
-            height,
-            start,
-            goal,
-            blizzards,
-            time: 0,
-            possible_positions,
+        let width = self.tiles.width() - 2;
+        let height = self.tiles.height() - 2;
+        let shift = |coord: usize, len: usize, back: bool| {
+            let offset = if back { len - time % len } else { time % len };
+            (coord - 1 + offset) % len + 1
         };
-        solver.calc_occupied();
-        solver
+        let blizzard_from =
+            |pos: Pos, direction: Direction| self.tiles[pos] == Tile::Blizzard(direction);
+        !(blizzard_from((shift(x, width, true), y), Direction::Right)
+            || blizzard_from((shift(x, width, false), y), Direction::Left)
+            || blizzard_from((x, shift(y, height, true)), Direction::Down)
+            || blizzard_from((x, shift(y, height, false)), Direction::Up))
     }
 
-    #[allow(dead_code)]
-    fn print_state(&mut self) {
-        let width = self.width;
-        let height = self.height;
-        let start = self.start;
-        let goal = self.goal;
-        let occupied = self.calc_occupied();
-        println!("\nTime: {}", self.time);
-        for y in 0..height {
-            for x in 0..width {
-                let point = (x, y);
-                if (x == 0 || y == 0 || x + 1 == width || y + 1 == height)
-                    && point != start
-                    && point != goal
-                {
-                    print!("#");
-                } else if occupied.contains(&point) {
-                    print!("X");
-                } else if self.possible_positions.contains(&point) {
-                    print!("E")
-                } else {
-                    print!(".")
-                }
-            }
-            println!()
-        }
+    /// The fewest minutes to get from `from` to `to` when starting at `time`, if there's a way.
+    fn travel(&self, from: Pos, to: Pos, time: usize) -> Option<usize> {
+        let neighbours = |(pos, time): &State| {
+            let time = (time + 1) % self.period;
+            std::iter::once(*pos)
+                .chain(self.tiles.neighbours_4(*pos))
+                .filter(move |next| self.is_free(*next, time))
+                .map(move |next| ((next, time), 1))
+        };
+        let distance = |((x, y), _): &State| x.abs_diff(to.0) + y.abs_diff(to.1);
+        search::astar(
+            [(from, time % self.period)],
+            neighbours,
+            distance,
+            |(pos, _)| *pos == to,
+        )
+        .map(|path| path.cost)
     }
-    fn run(&mut self, goal: &Point) -> usize {
-        // self.print_state();
-
-        for _ in 0.. {
-            self.time += 1;
-            self.move_blizzards();
-            let occupied = self.calc_occupied();
-            let mut next_possible = HashSet::new();
-            let mut maybe_insert = |p: Point| {
-                let on_wall =
-                    (p.0 == 0 || p.1 == 0 || p.0 + 1 == self.width || p.1 + 1 == self.height)
-                        && p != self.start
-                        && p != self.goal;
-                if !on_wall && !occupied.contains(&p) {
-                    next_possible.insert(p);
-                }
-            };
-            for (x, y) in &self.possible_positions {
-                maybe_insert((*x, *y));
-                if *x > 0 {
-                    maybe_insert((*x - 1, *y));
-                }
-                if *y > 0 {
-                    maybe_insert((*x, *y - 1));
-                }
-
-                maybe_insert((*x + 1, *y));
-
-                maybe_insert((*x, *y + 1));
-            }
-            self.possible_positions = next_possible;
-            // self.print_state();
-            // println!("Possible: {:#?}", self.possible_positions);
-            if self.possible_positions.contains(&goal) {
-                return self.time;
-            }
-        }
-        0
+
+    /// The minutes to the goal, back to the start and to the goal again.
+    fn round_trip(&self) -> Option<[usize; 3]> {
+        let there = self.travel(self.start, self.goal, 0)?;
+        let back = self.travel(self.goal, self.start, there)?;
+        let again = self.travel(self.start, self.goal, there + back)?;
+        Some([there, back, again])
     }
 }
 
-pub fn run() {
-    let mut solver = Solver::parse("24-input");
-    let goal = solver.goal;
-    let start = solver.start;
-    let part_1 = solver.run(&goal);
-    println!("Part 1: {}", part_1);
-    solver.possible_positions.clear();
-    solver.possible_positions.insert(goal);
-    solver.run(&start);
-    // println!("Back after: {}", back_after);
-    solver.possible_positions.clear();
-    solver.possible_positions.insert(start);
-    let part_2 = solver.run(&goal);
-    println!("Part 2: {}", part_2);
+pub struct Day24;
+
+impl Solution for Day24 {
+    type Input = Valley;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        Valley::parse(file)
+    }
+
+    fn part_1(valley: &Self::Input, _: &()) -> Answer {
+        valley
+            .travel(valley.start, valley.goal, 0)
+            .expect("No way through the valley")
+            .into()
+    }
+
+    fn part_2(valley: &Self::Input, _: &()) -> Answer {
+        let trips = valley.round_trip().expect("No way through the valley");
+        trips.iter().sum::<usize>().into()
+    }
+
+    /// A valley `width` tiles wide inside the walls and a quarter as high, but at most 25
+    /// tiles like the puzzle. The width is rounded up to a multiple of the height, so the
+    /// blizzards repeat every `width` minutes. Like in the puzzle, no blizzard moves up or
+    /// down in the columns of the entrance and the exit. Blizzards are left out if they would
+    /// run into a walk along the first row and down the last column, or back along the last
+    /// row and up the first column, each started at a multiple of the period. Waiting at the
+    /// entrance or the exit is always safe, so there's a way there and back.
+    fn generate(rng: &mut Rng, width: usize) -> Option<String> {
+        let height = (width / 4).clamp(1, 25);
+        let width = width.max(1).div_ceil(height) * height;
+        // The tiles of both walks with the minute they are on them, by row and by column.
+        let mut in_row = vec![Vec::new(); height + 1];
+        let mut in_col = vec![Vec::new(); width + 1];
+        let mut walk = |pos: Pos, time: usize| {
+            in_row[pos.1].push((pos.0, time));
+            in_col[pos.0].push((pos.1, time));
+        };
+        for x in 1..=width {
+            walk((x, 1), x);
+            walk((width + 1 - x, height), x);
+        }
+        for y in 1..=height {
+            walk((width, y), width - 1 + y);
+            walk((1, height + 1 - y), width - 1 + y);
+        }
+        // Where a blizzard starting at `start` is after `time` minutes, in a row or column
+        // of `len` tiles.
+        let moved = |start: usize, len: usize, time: usize, back: bool| {
+            let offset = if back { len - time % len } else { time % len };
+            (start - 1 + offset) % len + 1
+        };
+        let wall = |gap: usize| -> String {
+            (0..width + 2)
+                .map(|x| if x == gap { '.' } else { '#' })
+                .collect()
+        };
+        let mut lines = vec![wall(1)];
+        for (y, row_walks) in in_row.iter().enumerate().skip(1) {
+            let mut line = String::from("#");
+            for (x, col_walks) in in_col.iter().enumerate().skip(1) {
+                let blizzards: &[char] = if x == 1 || x == width {
+                    &['<', '>']
+                } else {
+                    &['<', '>', '^', 'v']
+                };
+                let blizzard = *rng.choose(blizzards);
+                let hits = match blizzard {
+                    '<' | '>' => row_walks
+                        .iter()
+                        .any(|(wx, time)| moved(x, width, *time, blizzard == '<') == *wx),
+                    _ => col_walks
+                        .iter()
+                        .any(|(wy, time)| moved(y, height, *time, blizzard == '^') == *wy),
+                };
+                line.push(if rng.chance(0.5) && !hits {
+                    blizzard
+                } else {
+                    '.'
+                });
+            }
+            line.push('#');
+            lines.push(line);
+        }
+        lines.push(wall(width));
+        Some(lines.iter().map(|line| format!("{}\n", line)).collect())
+    }
 }
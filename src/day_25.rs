@@ -1,10 +1,13 @@
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Solution};
 use crate::util;
+use crate::util::rng::Rng;
 
-type Num = i64;
+pub type Num = i64;
 
-fn snafu_to_num(s: &str) -> Num {
+pub fn snafu_to_num(s: &str) -> Result<Num> {
     let mut res = 0;
-    for c in s.chars() {
+    for (i, c) in s.chars().enumerate() {
         res *= 5;
         res += match c {
             '=' => -2,
@@ -12,13 +15,13 @@ fn snafu_to_num(s: &str) -> Num {
             '0' => 0,
             '1' => 1,
             '2' => 2,
-            _ => panic!("Illegal snafu character"),
+            _ => return Err(Error::invalid("Invalid SNAFU digit", c).at_column(i + 1)),
         }
     }
-    res
+    Ok(res)
 }
 
-fn num_to_snafu(x: Num) -> String {
+pub fn num_to_snafu(x: Num) -> String {
     if x == 0 {
         return "0".to_string();
     }
@@ -41,12 +44,32 @@ fn num_to_snafu(x: Num) -> String {
     reverse_res.chars().rev().collect()
 }
 
-pub fn run() {
-    let part_1 = util::read_lines("25-input")
-        .iter()
-        .map(|s| snafu_to_num(s))
-        .sum();
-    println!("Part 1: {}, {}", part_1, num_to_snafu(part_1));
+pub struct Day25;
+
+impl Solution for Day25 {
+    type Input = Vec<Num>;
+    type Config = ();
+
+    fn parse(file: &str) -> Result<Self::Input> {
+        util::lines(file)?.parse(snafu_to_num)
+    }
+
+    fn part_1(numbers: &Self::Input, _: &()) -> Answer {
+        num_to_snafu(numbers.iter().sum()).into()
+    }
+
+    /// Day 25 only has a single puzzle.
+    fn part_2(_: &Self::Input, _: &()) -> Answer {
+        "Merry Christmas!".into()
+    }
+
+    fn generate(rng: &mut Rng, len: usize) -> Option<String> {
+        Some(
+            (0..len)
+                .map(|_| num_to_snafu(rng.range(1..1_000_000_000_000)) + "\n")
+                .collect(),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -58,21 +81,46 @@ mod test {
 
     #[test]
     fn test_snafu_to_num() {
-        for line in util::read_lines("25-samples") {
+        for line in util::read_lines("25-samples").unwrap() {
             let tokens: Vec<&str> = line.split_whitespace().collect();
             let dezimal: Num = tokens[0].parse().unwrap();
-            let snafu = snafu_to_num(tokens[1]);
+            let snafu = snafu_to_num(tokens[1]).unwrap();
             assert_eq!(dezimal, snafu)
         }
     }
 
     #[test]
     fn test_num_to_snafu() {
-        for line in util::read_lines("25-samples") {
+        for line in util::read_lines("25-samples").unwrap() {
             let tokens: Vec<&str> = line.split_whitespace().collect();
             let dezimal_str = num_to_snafu(tokens[0].parse().unwrap());
             let snafu_str = tokens[1];
             assert_eq!(dezimal_str, snafu_str)
         }
     }
+
+    #[test]
+    fn test_invalid_snafu() {
+        let err = snafu_to_num("1=3-").unwrap_err();
+        assert_eq!(err, Error::invalid("Invalid SNAFU digit", '3').at_column(3));
+    }
+
+    #[test]
+    fn test_snafu_round_trip() {
+        let mut rng = Rng::new(25);
+        for digits in 1..=20 {
+            let bound = 5i64.pow(digits);
+            for _ in 0..100 {
+                let x = rng.range(-bound..bound);
+                let snafu = num_to_snafu(x);
+                assert_eq!(snafu_to_num(&snafu), Ok(x), "{} as {}", x, snafu);
+                assert!(
+                    snafu == "0" || !snafu.starts_with('0'),
+                    "{} as {}",
+                    x,
+                    snafu
+                );
+            }
+        }
+    }
 }
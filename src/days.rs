@@ -0,0 +1,100 @@
+use crate::error::Result;
+use crate::solution::{Answer, InputKind, Solution};
+use crate::util::rng::Rng;
+use crate::*;
+
+/// A parsed input of some day, with the day's type erased.
+pub trait Parsed {
+    fn part_1(&self) -> Answer;
+    fn part_2(&self) -> Answer;
+
+    fn part(&self, part: u8) -> Answer {
+        match part {
+            1 => self.part_1(),
+            2 => self.part_2(),
+            _ => panic!("Illegal part: {}", part),
+        }
+    }
+}
+
+struct ParsedInput<S: Solution> {
+    input: S::Input,
+    config: S::Config,
+}
+
+impl<S: Solution> Parsed for ParsedInput<S> {
+    fn part_1(&self) -> Answer {
+        S::part_1(&self.input, &self.config)
+    }
+
+    fn part_2(&self) -> Answer {
+        S::part_2(&self.input, &self.config)
+    }
+}
+
+fn parse<S: Solution + 'static>(file: &str, kind: InputKind) -> Result<Box<dyn Parsed>> {
+    let input = S::parse(file).map_err(|err| err.in_file(file))?;
+    Ok(Box::new(ParsedInput::<S> {
+        input,
+        config: S::config(kind),
+    }))
+}
+
+pub struct Day {
+    pub number: u8,
+    pub parse: fn(&str, InputKind) -> Result<Box<dyn Parsed>>,
+    pub generate: fn(&mut Rng, usize) -> Option<String>,
+}
+
+impl Day {
+    pub fn default_input(&self) -> String {
+        format!("{:02}-input", self.number)
+    }
+
+    /// Parses a data file, telling test inputs from puzzle inputs by the name.
+    pub fn parse_named(&self, file: &str) -> Result<Box<dyn Parsed>> {
+        (self.parse)(file, InputKind::of_name(file))
+    }
+}
+
+macro_rules! day {
+    ($number:expr, $solution:ty) => {
+        Day {
+            number: $number,
+            parse: parse::<$solution>,
+            generate: <$solution as Solution>::generate,
+        }
+    };
+}
+
+pub static DAYS: [Day; 25] = [
+    day!(1, day_01::Day01),
+    day!(2, day_02::Day02),
+    day!(3, day_03::Day03),
+    day!(4, day_04::Day04),
+    day!(5, day_05::Day05),
+    day!(6, day_06::Day06),
+    day!(7, day_07::Day07),
+    day!(8, day_08::Day08),
+    day!(9, day_09::Day09),
+    day!(10, day_10::Day10),
+    day!(11, day_11::Day11),
+    day!(12, day_12::Day12),
+    day!(13, day_13::Day13),
+    day!(14, day_14::Day14),
+    day!(15, day_15::Day15),
+    day!(16, day_16::Day16),
+    day!(17, day_17::Day17),
+    day!(18, day_18::Day18),
+    day!(19, day_19::Day19),
+    day!(20, day_20::Day20),
+    day!(21, day_21::Day21),
+    day!(22, day_22::Day22),
+    day!(23, day_23::Day23),
+    day!(24, day_24::Day24),
+    day!(25, day_25::Day25),
+];
+
+pub fn find(number: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|day| day.number == number)
+}
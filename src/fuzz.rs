@@ -0,0 +1,143 @@
+//! Feeds randomly broken copies of a day's data files to its parser, which should reject
+//! them with an error rather than panic or hang.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::days::Day;
+use crate::util;
+use crate::util::rng::Rng;
+
+/// How long parsing one input may take before it counts as hanging.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Characters the parsers give a meaning to, inserted besides those of the input itself.
+const SPECIAL: &str = "[],-=:; \n0123456789LRUD";
+
+/// An input the parser panicked or hung on.
+#[derive(Debug)]
+pub struct Failure {
+    pub input: String,
+    pub problem: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub runs: usize,
+    /// The runs where the parser returned an error.
+    pub rejected: usize,
+}
+
+/// Changes an input in a few random places: characters and whole lines are removed,
+/// repeated or replaced, numbers become huge or negative.
+pub fn mutate(rng: &mut Rng, input: &str) -> String {
+    let pool: Vec<char> = input.chars().chain(SPECIAL.chars()).collect();
+    let mut chars: Vec<char> = input.chars().collect();
+    for _ in 0..rng.range(1..5) {
+        let at = rng.index(chars.len() + 1);
+        match rng.index(7) {
+            0 => {
+                let end = (at + rng.range(1..9) as usize).min(chars.len());
+                chars.drain(at..end);
+            }
+            1 => chars.insert(at, *rng.choose(&pool)),
+            2 if at < chars.len() => chars[at] = *rng.choose(&pool),
+            3 => chars.truncate(at),
+            4 => {
+                let number = rng.choose(&["0", "-1", "99999999999999999999", "4294967296"]);
+                chars.splice(at..at, number.chars());
+            }
+            _ => {
+                let mut lines: Vec<String> = chars
+                    .iter()
+                    .collect::<String>()
+                    .split('\n')
+                    .map(str::to_string)
+                    .collect();
+                let line = rng.index(lines.len());
+                if rng.chance(0.5) {
+                    lines.remove(line);
+                } else {
+                    let copy = lines[line].clone();
+                    lines.insert(rng.index(lines.len() + 1), copy);
+                }
+                chars = lines.join("\n").chars().collect();
+            }
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Parses `runs` mutations of the day's test and puzzle inputs, stopping at the first one
+/// that panics or takes longer than [`TIMEOUT`].
+pub fn fuzz(day: &'static Day, runs: usize, seed: u64) -> Result<Stats, Failure> {
+    let corpus: Vec<String> = [format!("{:02}-test", day.number), day.default_input()]
+        .iter()
+        .filter_map(|input| util::read_str(input).ok())
+        .collect();
+    if corpus.is_empty() {
+        return Err(Failure {
+            input: String::new(),
+            problem: format!("No data files of day {} to start from", day.number),
+        });
+    }
+    let path = std::env::temp_dir().join(format!(
+        "rustvent-fuzz-{:02}-{}.txt",
+        day.number,
+        std::process::id()
+    ));
+    let mut rng = Rng::new(seed);
+    let res = fuzz_through(day, &corpus, &path, runs, &mut rng);
+    let _ = std::fs::remove_file(&path);
+    res
+}
+
+/// Parses `runs` mutations of the corpus, each written to the file at `path` first.
+fn fuzz_through(
+    day: &'static Day,
+    corpus: &[String],
+    path: &Path,
+    runs: usize,
+    rng: &mut Rng,
+) -> Result<Stats, Failure> {
+    let mut stats = Stats::default();
+    for _ in 0..runs {
+        let original = rng.choose(corpus);
+        let input = mutate(rng, original);
+        std::fs::write(path, &input).map_err(|err| Failure {
+            input: String::new(),
+            problem: format!("Failed to write {}: {}", path.display(), err),
+        })?;
+        match parse_in_time(day, path.to_path_buf()) {
+            Ok(rejected) => {
+                stats.runs += 1;
+                stats.rejected += usize::from(rejected);
+            }
+            Err(problem) => return Err(Failure { input, problem }),
+        }
+    }
+    Ok(stats)
+}
+
+/// Whether the parser rejected the file, or what went wrong.
+fn parse_in_time(day: &'static Day, path: PathBuf) -> Result<bool, String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let file = path.to_string_lossy();
+        let res = panic::catch_unwind(AssertUnwindSafe(|| day.parse_named(&file).is_err()));
+        let _ = sender.send(res.map_err(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            format!("Panicked: {}", message)
+        }));
+    });
+    receiver
+        .recv_timeout(TIMEOUT)
+        .unwrap_or_else(|_| Err(format!("Took longer than {:?}", TIMEOUT)))
+}
@@ -0,0 +1,38 @@
+//! Solutions to Advent of Code 2022. Every day implements [`solution::Solution`] and is
+//! registered in [`days::DAYS`]. The helpers they share live in the `rustvent_util` crate,
+//! re-exported as [`util`], [`error`] and [`mod@log`].
+
+pub mod answers;
+pub mod bench;
+pub mod day_01;
+pub mod day_02;
+pub mod day_03;
+pub mod day_04;
+pub mod day_05;
+pub mod day_06;
+pub mod day_07;
+pub mod day_08;
+pub mod day_09;
+pub mod day_10;
+pub mod day_11;
+pub mod day_12;
+pub mod day_13;
+pub mod day_14;
+pub mod day_15;
+pub mod day_16;
+pub mod day_17;
+pub mod day_18;
+pub mod day_19;
+pub mod day_20;
+pub mod day_21;
+pub mod day_22;
+pub mod day_23;
+pub mod day_24;
+pub mod day_25;
+pub mod days;
+pub mod fuzz;
+pub mod report;
+pub mod solution;
+
+pub use rustvent_util as util;
+pub use rustvent_util::{error, log};
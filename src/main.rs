@@ -1,9 +1,773 @@
-mod day_16;
+use std::io::Write;
 
-#[allow(unused_imports)]
-mod util;
+use rustvent_2022::days::{self, Day};
+use rustvent_2022::solution::InputKind;
+use rustvent_2022::util::parallel;
+use rustvent_2022::util::rng::Rng;
+use rustvent_2022::{answers, bench, day_01, day_02, day_05, fuzz, log, report, util};
+
+const USAGE: &str = "Usage:
+    rustvent_2022 [<OPTIONS>] run --day <DAY> [--part <PART>] [--input <INPUT>] [--test] [--format <FORMAT>]
+    rustvent_2022 [<OPTIONS>] run --all [--format <FORMAT>]
+    rustvent_2022 [<OPTIONS>] verify [--day <DAY>]
+    rustvent_2022 [<OPTIONS>] bench --day <DAY> [--input <INPUT>] [--test] [--runs <RUNS>]
+    rustvent_2022 [<OPTIONS>] bench --all [--runs <RUNS>]
+    rustvent_2022 [<OPTIONS>] generate --day <DAY> [--size <SIZE>] [--seed <SEED>] [--output <FILE>]
+    rustvent_2022 [<OPTIONS>] fuzz --day <DAY> [--runs <RUNS>] [--seed <SEED>]
+    rustvent_2022 [<OPTIONS>] calories (--top <K> | --above <CALORIES> | --breakdown) [--input <INPUT>]
+    rustvent_2022 [<OPTIONS>] rps [--rules <RULES>] [--input <INPUT>] [--breakdown] [--best]
+    rustvent_2022 [<OPTIONS>] crates [--crane <MODEL> | --capacity <CRATES>] [--steps <STEPS>] [--input <INPUT>]
+
+Options:
+    --data-dir <DIR>    Where named inputs are read from
+    --log <SPEC>        Log to stderr, e.g. day22=trace or debug,day16=off
+    --jobs <JOBS>       Threads to solve days and the slow parts of days 15, 16 and 19 on
+
+<INPUT> is a file name in the data directory without .txt, a path or - for stdin.
+The data directory defaults to $RUSTVENT_DATA_DIR, then to data.
+--test solves <INPUT> with the parameters of the example, like the row of day 15. Without
+it only inputs with test in their name are treated as examples.
+<FORMAT> is text (the default) or json, which prints one JSON object per part and line.
+generate writes a random input of the day to stdout or <FILE>. <SIZE> is what the input
+grows with, e.g. lines or the side of a map (1000 by default), <SEED> defaults to 0.
+fuzz parses <RUNS> (1000 by default) broken copies of the day's data files and fails if
+the parser panics or hangs instead of returning an error.
+calories queries the elves of day 1: the <K> carrying the most calories, all carrying more
+than <CALORIES> or the food of every elf.
+rps scores the strategy guide of day 2 by the <RULES> of a game like rock paper scissors,
+with every round or the best reading of the second column.
+crates draws the stacks of day 5 after the first <STEPS> steps (all by default), moved by
+the CrateMover <MODEL> 9000 (the default) or 9001, or by a crane lifting up to <CRATES>
+crates at once.";
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Default)]
+struct RunArgs {
+    day: Option<u8>,
+    part: Option<u8>,
+    input: Option<String>,
+    all: bool,
+    test: bool,
+    format: Format,
+}
+
+impl RunArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut run_args = Self::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--day" => run_args.day = Some(parse_number(next_value(arg, &mut iter)?, 1..=25)?),
+                "--part" => run_args.part = Some(parse_number(next_value(arg, &mut iter)?, 1..=2)?),
+                "--input" => run_args.input = Some(next_value(arg, &mut iter)?.to_string()),
+                "--all" => run_args.all = true,
+                "--test" => run_args.test = true,
+                "--format" => {
+                    run_args.format = match next_value(arg, &mut iter)?.as_str() {
+                        "text" => Format::Text,
+                        "json" => Format::Json,
+                        format => return Err(format!("Unknown format: {}", format)),
+                    }
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        if run_args.all == run_args.day.is_some() {
+            return Err("Expected either --day or --all".to_string());
+        }
+        if run_args.all && (run_args.part.is_some() || run_args.input.is_some() || run_args.test) {
+            return Err("--part, --input and --test can't be combined with --all".to_string());
+        }
+        Ok(run_args)
+    }
+}
+
+#[derive(Default)]
+struct VerifyArgs {
+    day: Option<u8>,
+}
+
+impl VerifyArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut verify_args = Self::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--day" => {
+                    verify_args.day = Some(parse_number(next_value(arg, &mut iter)?, 1..=25)?)
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        Ok(verify_args)
+    }
+}
+
+struct BenchArgs {
+    day: Option<u8>,
+    input: Option<String>,
+    all: bool,
+    test: bool,
+    runs: usize,
+}
+
+impl BenchArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut bench_args = Self {
+            day: None,
+            input: None,
+            all: false,
+            test: false,
+            runs: 5,
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--day" => {
+                    bench_args.day = Some(parse_number(next_value(arg, &mut iter)?, 1..=25)?)
+                }
+                "--input" => bench_args.input = Some(next_value(arg, &mut iter)?.to_string()),
+                "--all" => bench_args.all = true,
+                "--test" => bench_args.test = true,
+                "--runs" => bench_args.runs = positive_arg(arg, &mut iter)?,
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        if bench_args.all == bench_args.day.is_some() {
+            return Err("Expected either --day or --all".to_string());
+        }
+        if bench_args.all && (bench_args.input.is_some() || bench_args.test) {
+            return Err("--input and --test can't be combined with --all".to_string());
+        }
+        Ok(bench_args)
+    }
+}
+
+struct GenerateArgs {
+    day: u8,
+    size: usize,
+    seed: u64,
+    output: Option<String>,
+}
+
+impl GenerateArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut day = None;
+        let mut generate_args = Self {
+            day: 0,
+            size: 1000,
+            seed: 0,
+            output: None,
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--day" => day = Some(parse_number(next_value(arg, &mut iter)?, 1..=25)?),
+                "--size" => generate_args.size = positive_arg(arg, &mut iter)?,
+                "--seed" => generate_args.seed = parse_seed(next_value(arg, &mut iter)?)?,
+                "--output" => generate_args.output = Some(next_value(arg, &mut iter)?.to_string()),
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        generate_args.day = day.ok_or("Expected --day")?;
+        Ok(generate_args)
+    }
+}
+
+struct FuzzArgs {
+    day: u8,
+    runs: usize,
+    seed: u64,
+}
+
+impl FuzzArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut day = None;
+        let mut fuzz_args = Self {
+            day: 0,
+            runs: 1000,
+            seed: 0,
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--day" => day = Some(parse_number(next_value(arg, &mut iter)?, 1..=25)?),
+                "--runs" => fuzz_args.runs = positive_arg(arg, &mut iter)?,
+                "--seed" => fuzz_args.seed = parse_seed(next_value(arg, &mut iter)?)?,
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        fuzz_args.day = day.ok_or("Expected --day")?;
+        Ok(fuzz_args)
+    }
+}
+
+#[derive(Default)]
+struct RpsArgs {
+    rules: Option<String>,
+    input: Option<String>,
+    breakdown: bool,
+    best: bool,
+}
+
+impl RpsArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut rps_args = Self::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--rules" => rps_args.rules = Some(next_value(arg, &mut iter)?.to_string()),
+                "--input" => rps_args.input = Some(next_value(arg, &mut iter)?.to_string()),
+                "--breakdown" => rps_args.breakdown = true,
+                "--best" => rps_args.best = true,
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        Ok(rps_args)
+    }
+}
+
+enum CraneArg {
+    Model(u16),
+    Capacity(usize),
+}
+
+struct CratesArgs {
+    crane: CraneArg,
+    steps: Option<usize>,
+    input: Option<String>,
+}
+
+impl CratesArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut crane = None;
+        let mut crates_args = Self {
+            crane: CraneArg::Model(9000),
+            steps: None,
+            input: None,
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let new_crane = match arg.as_str() {
+                "--crane" => match next_value(arg, &mut iter)?.as_str() {
+                    "9000" => CraneArg::Model(9000),
+                    "9001" => CraneArg::Model(9001),
+                    other => return Err(format!("Expected 9000 or 9001, got {}", other)),
+                },
+                "--capacity" => CraneArg::Capacity(positive_arg(arg, &mut iter)?),
+                "--steps" => {
+                    let value = next_value(arg, &mut iter)?;
+                    crates_args.steps = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Expected a number, got {}", value))?,
+                    );
+                    continue;
+                }
+                "--input" => {
+                    crates_args.input = Some(next_value(arg, &mut iter)?.to_string());
+                    continue;
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            };
+            if crane.replace(new_crane).is_some() {
+                return Err("Expected only one of --crane and --capacity".to_string());
+            }
+        }
+        if let Some(crane) = crane {
+            crates_args.crane = crane;
+        }
+        Ok(crates_args)
+    }
+}
+
+/// What `calories` asks about the elves of day 1.
+enum CaloriesQuery {
+    /// The `k` elves carrying the most calories.
+    Top(usize),
+    /// All elves carrying more than that many calories.
+    Above(i32),
+    /// The food of every elf.
+    Breakdown,
+}
+
+struct CaloriesArgs {
+    query: CaloriesQuery,
+    input: Option<String>,
+}
+
+impl CaloriesArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut query = None;
+        let mut input = None;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let new_query = match arg.as_str() {
+                "--top" => CaloriesQuery::Top(positive_arg(arg, &mut iter)?),
+                "--above" => {
+                    let value = next_value(arg, &mut iter)?;
+                    CaloriesQuery::Above(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Expected a number, got {}", value))?,
+                    )
+                }
+                "--breakdown" => CaloriesQuery::Breakdown,
+                "--input" => {
+                    input = Some(next_value(arg, &mut iter)?.to_string());
+                    continue;
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            };
+            if query.replace(new_query).is_some() {
+                return Err("Expected only one of --top, --above and --breakdown".to_string());
+            }
+        }
+        let query = query.ok_or("Expected one of --top, --above and --breakdown")?;
+        Ok(Self { query, input })
+    }
+}
+
+/// The value of the option `arg`, which is the next argument.
+fn next_value<'a>(
+    arg: &str,
+    iter: &mut impl Iterator<Item = &'a String>,
+) -> Result<&'a String, String> {
+    iter.next()
+        .ok_or_else(|| format!("Missing value for {}", arg))
+}
+
+/// The value of the option `arg` as a number above zero.
+fn positive_arg<'a>(
+    arg: &str,
+    iter: &mut impl Iterator<Item = &'a String>,
+) -> Result<usize, String> {
+    let value = next_value(arg, iter)?;
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("Expected a positive number, got {}", value)),
+    }
+}
+
+fn parse_seed(s: &str) -> Result<u64, String> {
+    s.parse()
+        .map_err(|_| format!("Expected a seed from 0 to {}, got {}", u64::MAX, s))
+}
+
+fn parse_number(s: &str, range: std::ops::RangeInclusive<u8>) -> Result<u8, String> {
+    match s.parse() {
+        Ok(n) if range.contains(&n) => Ok(n),
+        _ => Err(format!(
+            "Expected a number from {} to {}, got {}",
+            range.start(),
+            range.end(),
+            s
+        )),
+    }
+}
+
+/// Test inputs are told apart by their name unless `--test` says so.
+fn input_kind(input: &str, test: bool) -> InputKind {
+    if test {
+        InputKind::Test
+    } else {
+        InputKind::of_name(input)
+    }
+}
+
+/// Prints the answers of a run, reporting whether it succeeded. Errors go to stderr unless
+/// they are part of the JSON records.
+fn print_run(run: &report::Run, format: Format) -> bool {
+    match (format, &run.result) {
+        (Format::Json, _) => {
+            for line in run.to_json_lines() {
+                println!("{}", line);
+            }
+        }
+        (Format::Text, Ok(part_runs)) => {
+            for part_run in part_runs {
+                println!(
+                    "Day {:02}, part {} ({}): {}",
+                    run.day, part_run.part, run.input, part_run.answer
+                );
+            }
+        }
+        (Format::Text, Err(err)) => eprintln!("Error: {}", err),
+    }
+    !run.is_failure()
+}
+
+/// Runs the requested days, reporting whether all of them succeeded. The JSON records
+/// compare the answers with the answers file.
+fn run(run_args: RunArgs) -> bool {
+    let entries = match run_args.format {
+        Format::Text => Vec::new(),
+        Format::Json => answers::load().unwrap_or_else(|err| {
+            eprintln!("Error: {}", err);
+            Vec::new()
+        }),
+    };
+    let expected = |input: &str| entries.iter().find(|entry| entry.input == input);
+    let selected: Vec<(&Day, Vec<u8>, String)> = if run_args.all {
+        days::DAYS
+            .iter()
+            .map(|day| (day, vec![1, 2], day.default_input()))
+            .collect()
+    } else {
+        let day = days::find(run_args.day.unwrap()).expect("Day not registered");
+        let parts = match run_args.part {
+            Some(part) => vec![part],
+            None => vec![1, 2],
+        };
+        let input = run_args.input.unwrap_or_else(|| day.default_input());
+        vec![(day, parts, input)]
+    };
+    let run_one = |(day, parts, input): &(&Day, Vec<u8>, String)| {
+        report::run(
+            day,
+            parts,
+            input,
+            input_kind(input, run_args.test),
+            expected(input),
+        )
+    };
+    // A single day keeps all jobs for the parts it spreads over threads itself.
+    let runs = match &selected[..] {
+        [one] => vec![run_one(one)],
+        _ => parallel::map(&selected, run_one),
+    };
+    let mut success = true;
+    for run in &runs {
+        success &= print_run(run, run_args.format);
+    }
+    success
+}
+
+/// Checks the answers of every data file in the answers file, reporting whether all of them
+/// are right.
+fn verify(verify_args: VerifyArgs) -> bool {
+    let entries = match answers::load() {
+        Ok(entries) => entries,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return false;
+        }
+    };
+    let entries: Vec<answers::Entry> = entries
+        .into_iter()
+        .filter(|entry| verify_args.day.is_none_or(|day| day == entry.day))
+        .collect();
+    let results = parallel::map(&entries, answers::Entry::verify);
+    let mut success = true;
+    for (entry, result) in entries.iter().zip(results) {
+        match result {
+            Ok(outcomes) => {
+                for outcome in outcomes {
+                    let result = if outcome.is_ok() {
+                        "ok".to_string()
+                    } else {
+                        success = false;
+                        format!(
+                            "FAILED, got {:?}, expected {:?}",
+                            outcome.actual.to_string(),
+                            outcome.expected.to_string()
+                        )
+                    };
+                    println!(
+                        "Day {:02}, part {} ({}): {}",
+                        entry.day, outcome.part, entry.input, result
+                    );
+                }
+            }
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                success = false;
+            }
+        }
+    }
+    success
+}
+
+/// Times the requested days and prints a table of the results, reporting whether all of
+/// them succeeded.
+fn bench(bench_args: BenchArgs) -> bool {
+    let selected: Vec<(&Day, String)> = if bench_args.all {
+        days::DAYS
+            .iter()
+            .map(|day| (day, day.default_input()))
+            .collect()
+    } else {
+        let day = days::find(bench_args.day.unwrap()).expect("Day not registered");
+        let input = bench_args.input.unwrap_or_else(|| day.default_input());
+        vec![(day, input)]
+    };
+    let mut success = true;
+    bench::print_header();
+    for (day, input) in selected {
+        let kind = input_kind(&input, bench_args.test);
+        match bench::bench(day, &input, kind, bench_args.runs) {
+            Ok(timings) => {
+                for timings in &timings {
+                    bench::print_row(day, &input, timings);
+                }
+            }
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                success = false;
+            }
+        }
+    }
+    success
+}
+
+/// Writes a random input of a day, reporting whether the day has a generator.
+fn generate(generate_args: GenerateArgs) -> bool {
+    let day = days::find(generate_args.day).expect("Day not registered");
+    let mut rng = Rng::new(generate_args.seed);
+    let Some(input) = (day.generate)(&mut rng, generate_args.size) else {
+        eprintln!("Error: Day {} has no generator", day.number);
+        return false;
+    };
+    let written = match &generate_args.output {
+        Some(path) => {
+            std::fs::write(path, input).map_err(|err| format!("Failed to write {}: {}", path, err))
+        }
+        None => std::io::stdout()
+            .write_all(input.as_bytes())
+            .map_err(|err| format!("Failed to write stdout: {}", err)),
+    };
+    if let Err(err) = written {
+        eprintln!("Error: {}", err);
+        return false;
+    }
+    true
+}
+
+/// Fuzzes the parser of a day, reporting whether it only ever returned errors.
+fn fuzz(fuzz_args: FuzzArgs) -> bool {
+    let day = days::find(fuzz_args.day).expect("Day not registered");
+    // The panics are reported with the input that caused them.
+    std::panic::set_hook(Box::new(|_| ()));
+    match fuzz::fuzz(day, fuzz_args.runs, fuzz_args.seed) {
+        Ok(stats) => {
+            println!(
+                "Day {:02}: {} runs, {} rejected",
+                day.number, stats.runs, stats.rejected
+            );
+            true
+        }
+        Err(failure) => {
+            eprintln!("Error: {}, input:\n{}", failure.problem, failure.input);
+            false
+        }
+    }
+}
+
+/// Prints the elves of day 1 the query asks for.
+fn print_calories(input: &str, query: &CaloriesQuery) -> util::error::Result<()> {
+    match query {
+        CaloriesQuery::Top(k) => {
+            for (rank, elf) in day_01::top_elves(input, *k)?.iter().enumerate() {
+                println!("{}. Elf {}: {}", rank + 1, elf.number, elf.total());
+            }
+        }
+        CaloriesQuery::Above(calories) => {
+            for elf in day_01::elves_above(input, *calories)? {
+                println!("Elf {}: {}", elf.number, elf.total());
+            }
+        }
+        CaloriesQuery::Breakdown => {
+            for elf in day_01::elves(input)? {
+                let elf = elf?;
+                let items: Vec<String> = elf.items.iter().map(i32::to_string).collect();
+                println!(
+                    "Elf {}: {} = {}",
+                    elf.number,
+                    elf.total(),
+                    items.join(" + ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Answers a query about the elves of day 1, reporting whether the input could be read.
+fn calories(calories_args: CaloriesArgs) -> bool {
+    let input = calories_args.input.as_deref().unwrap_or("01-input");
+    if let Err(err) = print_calories(input, &calories_args.query) {
+        eprintln!("Error: {}", err.in_file(input));
+        return false;
+    }
+    true
+}
+
+/// Prints the scores of a strategy guide of day 2 by shape and by outcome, with every
+/// round if `breakdown` is set and the best reading of the second column if `best` is. A
+/// reading with a round it can't score is left out, and the first such error is returned
+/// once the rest is printed.
+fn print_rps(
+    game: day_02::Game,
+    input: &str,
+    breakdown: bool,
+    best: bool,
+) -> util::error::Result<()> {
+    let guide = day_02::Guide::parse(game, input)?;
+    let mut failed = None;
+    let readings = [
+        ("shape", guide.rounds_by_shape()),
+        ("outcome", guide.rounds_by_outcome()),
+    ];
+    for (reading, rounds) in readings {
+        match rounds {
+            Ok(rounds) => {
+                let total: i32 = rounds.iter().map(|round| round.score).sum();
+                println!("By {}: {}", reading, total);
+                if breakdown {
+                    for (i, round) in rounds.iter().enumerate() {
+                        println!(
+                            "    Round {}: {} against {}, {}: {}",
+                            i + 1,
+                            guide.game.shapes[round.me].name,
+                            guide.game.shapes[round.opponent].name,
+                            round.outcome.as_str(),
+                            round.score
+                        );
+                    }
+                }
+            }
+            Err(err) => {
+                failed.get_or_insert(err);
+            }
+        }
+    }
+    if best {
+        match guide.best_interpretation() {
+            Some(best) => {
+                let shapes: Vec<String> = best
+                    .shapes
+                    .iter()
+                    .map(|(letter, shape)| {
+                        format!("{} = {}", letter, guide.game.shapes[*shape].name)
+                    })
+                    .collect();
+                println!("Best reading: {}: {}", shapes.join(", "), best.score);
+            }
+            None => println!("Best reading: more letters than shapes"),
+        }
+    }
+    failed.map_or(Ok(()), Err)
+}
+
+/// Scores a strategy guide of day 2, reporting whether the rules and the guide could be read.
+fn rps(rps_args: RpsArgs) -> bool {
+    let input = rps_args.input.as_deref().unwrap_or("02-input");
+    let game = match &rps_args.rules {
+        Some(rules) => day_02::Game::parse(rules).map_err(|err| err.in_file(rules)),
+        None => Ok(day_02::Game::classic()),
+    };
+    let res = game.and_then(|game| {
+        print_rps(game, input, rps_args.breakdown, rps_args.best).map_err(|err| err.in_file(input))
+    });
+    if let Err(err) = res {
+        eprintln!("Error: {}", err);
+        return false;
+    }
+    true
+}
+
+/// Draws the stacks of day 5 after the first `steps` steps, all if `None`, and prints
+/// their top crates.
+fn print_crates(
+    input: &str,
+    crane: &dyn day_05::Crane,
+    steps: Option<usize>,
+) -> util::error::Result<()> {
+    let parsed = day_05::Input::parse(input)?;
+    let steps = steps.unwrap_or(parsed.steps.len());
+    if steps > parsed.steps.len() {
+        return Err(util::error::Error::new(format!(
+            "Expected at most {} steps, got {}",
+            parsed.steps.len(),
+            steps
+        )));
+    }
+    let state = parsed.state_after(crane, steps)?;
+    println!("{}", state);
+    println!("Top crates: {}", state.result());
+    Ok(())
+}
+
+/// Draws the stacks of day 5 after some steps, reporting whether the input could be read
+/// and the steps done.
+fn crates(crates_args: CratesArgs) -> bool {
+    let input = crates_args.input.as_deref().unwrap_or("05-input");
+    let crane: Box<dyn day_05::Crane> = match crates_args.crane {
+        CraneArg::Model(9000) => Box::new(day_05::CrateMover9000),
+        CraneArg::Model(_) => Box::new(day_05::CrateMover9001),
+        CraneArg::Capacity(capacity) => match day_05::LimitedCrane::new(capacity) {
+            Ok(crane) => Box::new(crane),
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                return false;
+            }
+        },
+    };
+    if let Err(err) = print_crates(input, crane.as_ref(), crates_args.steps) {
+        eprintln!("Error: {}", err.in_file(input));
+        return false;
+    }
+    true
+}
+
+/// Applies the options before the command, returning the command and its arguments.
+fn parse_global_args(mut args: &[String]) -> Result<&[String], String> {
+    while let Some(arg) = args.first() {
+        match arg.as_str() {
+            "--data-dir" => {
+                util::set_data_dir(next_value(arg, &mut args[1..].iter())?);
+                args = &args[2..];
+            }
+            "--jobs" => {
+                parallel::set_jobs(positive_arg(arg, &mut args[1..].iter())?);
+                args = &args[2..];
+            }
+            "--log" => {
+                let spec = next_value(arg, &mut args[1..].iter())?;
+                log::configure(spec).map_err(|err| err.to_string())?;
+                args = &args[2..];
+            }
+            _ => break,
+        }
+    }
+    Ok(args)
+}
 
-#[allow(unused_variables)]
 fn main() {
-    day_16::run()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let res = parse_global_args(&args).and_then(|args| match args.first().map(String::as_str) {
+        Some("run") => RunArgs::parse(&args[1..]).map(run),
+        Some("verify") => VerifyArgs::parse(&args[1..]).map(verify),
+        Some("bench") => BenchArgs::parse(&args[1..]).map(bench),
+        Some("generate") => GenerateArgs::parse(&args[1..]).map(generate),
+        Some("fuzz") => FuzzArgs::parse(&args[1..]).map(fuzz),
+        Some("calories") => CaloriesArgs::parse(&args[1..]).map(calories),
+        Some("rps") => RpsArgs::parse(&args[1..]).map(rps),
+        Some("crates") => CratesArgs::parse(&args[1..]).map(crates),
+        _ => Err("Expected a command".to_string()),
+    });
+    match res {
+        Ok(true) => (),
+        Ok(false) => std::process::exit(1),
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    }
 }
@@ -0,0 +1,232 @@
+use std::time::Duration;
+
+use crate::answers::Entry;
+use crate::bench;
+use crate::days::Day;
+use crate::error::Result;
+use crate::solution::{Answer, InputKind};
+
+/// How a part fared against the answers file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// The answer differs from the expected one.
+    Mismatch,
+    /// There is no expected answer to compare with.
+    Unchecked,
+    /// The input couldn't be parsed.
+    Error,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Mismatch => "mismatch",
+            Status::Unchecked => "unchecked",
+            Status::Error => "error",
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Status::Mismatch | Status::Error)
+    }
+}
+
+pub struct PartRun {
+    pub part: u8,
+    pub answer: Answer,
+    pub expected: Option<Answer>,
+    pub duration: Duration,
+}
+
+impl PartRun {
+    pub fn status(&self) -> Status {
+        match &self.expected {
+            None => Status::Unchecked,
+            Some(expected) if *expected == self.answer => Status::Ok,
+            Some(_) => Status::Mismatch,
+        }
+    }
+}
+
+/// Parsing one input of a day and solving some of its parts.
+pub struct Run {
+    pub day: u8,
+    pub input: String,
+    pub parts: Vec<u8>,
+    pub parse_duration: Duration,
+    pub result: Result<Vec<PartRun>>,
+}
+
+/// Runs the parts of a day, comparing them with `expected` if given.
+pub fn run(day: &Day, parts: &[u8], input: &str, kind: InputKind, expected: Option<&Entry>) -> Run {
+    let (parsed, parse_duration) = bench::time(|| (day.parse)(input, kind));
+    let result = parsed.map(|parsed| {
+        parts
+            .iter()
+            .map(|part| {
+                let (answer, duration) = bench::time(|| parsed.part(*part));
+                PartRun {
+                    part: *part,
+                    answer,
+                    expected: expected.and_then(|entry| entry.answer(*part)).cloned(),
+                    duration,
+                }
+            })
+            .collect()
+    });
+    Run {
+        day: day.number,
+        input: input.to_string(),
+        parts: parts.to_vec(),
+        parse_duration,
+        result,
+    }
+}
+
+impl Run {
+    pub fn is_failure(&self) -> bool {
+        match &self.result {
+            Ok(part_runs) => part_runs
+                .iter()
+                .any(|part_run| part_run.status().is_failure()),
+            Err(_) => true,
+        }
+    }
+
+    /// One JSON object per part, each on its own line.
+    pub fn to_json_lines(&self) -> Vec<String> {
+        match &self.result {
+            Ok(part_runs) => part_runs
+                .iter()
+                .map(|part_run| {
+                    self.json_record(
+                        part_run.part,
+                        part_run.status(),
+                        &[
+                            ("answer", json_answer(Some(&part_run.answer))),
+                            ("expected", json_answer(part_run.expected.as_ref())),
+                            ("duration_ms", json_ms(part_run.duration)),
+                        ],
+                    )
+                })
+                .collect(),
+            Err(err) => self
+                .parts
+                .iter()
+                .map(|part| {
+                    self.json_record(
+                        *part,
+                        Status::Error,
+                        &[("error", json_string(&err.to_string()))],
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn json_record(&self, part: u8, status: Status, fields: &[(&str, String)]) -> String {
+        let mut record = format!(
+            "{{\"day\": {}, \"part\": {}, \"input\": {}, \"status\": {}, \"parse_ms\": {}",
+            self.day,
+            part,
+            json_string(&self.input),
+            json_string(status.as_str()),
+            json_ms(self.parse_duration)
+        );
+        for (key, value) in fields {
+            record.push_str(&format!(", {}: {}", json_string(key), value));
+        }
+        record.push('}');
+        record
+    }
+}
+
+fn json_answer(answer: Option<&Answer>) -> String {
+    match answer {
+        None => "null".to_string(),
+        Some(Answer::Int(x)) => x.to_string(),
+        Some(Answer::Str(s)) => json_string(s),
+    }
+}
+
+fn json_ms(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\t' => json.push_str("\\t"),
+            '\r' => json.push_str("\\r"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn run_with(result: Result<Vec<PartRun>>) -> Run {
+        Run {
+            day: 10,
+            input: "10-test".to_string(),
+            parts: vec![1, 2],
+            parse_duration: Duration::from_micros(1500),
+            result,
+        }
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(json_string("\n#.\u{1}"), "\"\\n#.\\u0001\"");
+    }
+
+    #[test]
+    fn test_to_json_lines() {
+        let run = run_with(Ok(vec![
+            PartRun {
+                part: 1,
+                answer: 42.into(),
+                expected: Some(42.into()),
+                duration: Duration::from_millis(2),
+            },
+            PartRun {
+                part: 2,
+                answer: "\n#".into(),
+                expected: None,
+                duration: Duration::ZERO,
+            },
+        ]));
+        assert!(!run.is_failure());
+        assert_eq!(
+            run.to_json_lines(),
+            [
+                "{\"day\": 10, \"part\": 1, \"input\": \"10-test\", \"status\": \"ok\", \
+                 \"parse_ms\": 1.500, \"answer\": 42, \"expected\": 42, \"duration_ms\": 2.000}",
+                "{\"day\": 10, \"part\": 2, \"input\": \"10-test\", \"status\": \"unchecked\", \
+                 \"parse_ms\": 1.500, \"answer\": \"\\n#\", \"expected\": null, \
+                 \"duration_ms\": 0.000}",
+            ]
+        );
+        let run = run_with(Err(Error::new("Broken")));
+        assert!(run.is_failure());
+        assert_eq!(
+            run.to_json_lines()[1],
+            "{\"day\": 10, \"part\": 2, \"input\": \"10-test\", \"status\": \"error\", \
+             \"parse_ms\": 1.500, \"error\": \"Broken\"}"
+        );
+    }
+}
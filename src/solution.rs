@@ -0,0 +1,89 @@
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::util::rng::Rng;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    Int(i64),
+    Str(String),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Int(x) => write!(f, "{}", x),
+            Answer::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(x: $t) -> Self {
+                    Answer::Int(i64::try_from(x).expect("Answer doesn't fit into i64"))
+                }
+            }
+        )*
+    };
+}
+
+answer_from_int!(i32, i64, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Str(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Str(s.to_string())
+    }
+}
+
+/// Whether an input is an example from the puzzle text or a full puzzle input, which some
+/// days solve with different parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputKind {
+    Test,
+    Puzzle,
+}
+
+impl InputKind {
+    /// Guesses the kind from a data file name like `15-test`. Inputs read from stdin or other
+    /// paths can't be told apart this way and have to be given their kind.
+    pub fn of_name(name: &str) -> Self {
+        if name.contains("test") {
+            InputKind::Test
+        } else {
+            InputKind::Puzzle
+        }
+    }
+}
+
+/// A day's puzzle: the input is parsed once and then shared by both parts.
+pub trait Solution {
+    type Input;
+    /// Extra parameters of a puzzle, like the row to scan on day 15.
+    type Config: Default;
+
+    /// Some puzzles use different parameters for their test inputs.
+    fn config(_kind: InputKind) -> Self::Config {
+        Self::Config::default()
+    }
+
+    fn parse(file: &str) -> Result<Self::Input>;
+
+    fn part_1(input: &Self::Input, config: &Self::Config) -> Answer;
+
+    fn part_2(input: &Self::Input, config: &Self::Config) -> Answer;
+
+    /// A random input the parts can solve, `None` if the day has no generator. `size` is
+    /// what the input grows with, like the number of lines or the side of a map.
+    fn generate(_rng: &mut Rng, _size: usize) -> Option<String> {
+        None
+    }
+}
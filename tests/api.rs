@@ -0,0 +1,82 @@
+use rustvent_2022::day_13::Expr;
+use rustvent_2022::day_25::{num_to_snafu, snafu_to_num};
+use rustvent_2022::days;
+use rustvent_2022::solution::{Answer, InputKind};
+use rustvent_2022::util::parallel;
+use rustvent_2022::util::rng::Rng;
+
+#[test]
+fn test_day_13_expr() {
+    let left = Expr::parse("[1,[2,[3]]]").unwrap();
+    let right = Expr::parse("[1,[2,4]]").unwrap();
+    assert_eq!(left.compare(&right), -1);
+    assert_eq!(right.compare(&left), 1);
+}
+
+#[test]
+fn test_day_25_snafu() {
+    assert_eq!(num_to_snafu(2022), "1=11-2");
+    assert_eq!(snafu_to_num("1=11-2").unwrap(), 2022);
+}
+
+#[test]
+fn test_registered_day() {
+    let day = days::find(1).unwrap();
+    let parsed = day.parse_named("01-test").unwrap();
+    assert_eq!(parsed.part(1), Answer::Int(24000));
+}
+
+#[test]
+fn test_input_kind() {
+    let day = days::find(15).unwrap();
+    let path = std::env::temp_dir().join("rustvent-example-15.txt");
+    std::fs::copy("data/15-test.txt", &path).unwrap();
+    let parsed = (day.parse)(path.to_str().unwrap(), InputKind::Test).unwrap();
+    assert_eq!(parsed.part(1), Answer::Int(26));
+    assert_eq!(parsed.part(2), Answer::Int(56000011));
+}
+
+/// Part 1 of day 22 walks the flat map, which doesn't need to fold into a cube.
+#[test]
+fn test_day_22_flat_map() {
+    let day = days::find(22).unwrap();
+    let path = std::env::temp_dir().join("rustvent-flat-22.txt");
+    std::fs::write(&path, "...\n.#.\n...\n\n2R1\n").unwrap();
+    let parsed = day.parse_named(path.to_str().unwrap()).unwrap();
+    assert_eq!(parsed.part(1), Answer::Int(2013));
+}
+
+/// Days 15, 16 and 19 spread their parts over threads when started outside of a worker.
+#[test]
+fn test_parallel_days() {
+    let solve = || -> Vec<Answer> {
+        [15, 16, 19]
+            .iter()
+            .flat_map(|number| {
+                let day = days::find(*number).unwrap();
+                let parsed = day.parse_named(&format!("{:02}-test", number)).unwrap();
+                [parsed.part(1), parsed.part(2)]
+            })
+            .collect()
+    };
+    let sequential = solve();
+    parallel::set_jobs(4);
+    assert_eq!(parallel::jobs(), 4);
+    let parallel = solve();
+    parallel::set_jobs(1);
+    assert_eq!(parallel, sequential);
+}
+
+#[test]
+fn test_generated_inputs() {
+    for day in &days::DAYS {
+        let mut rng = Rng::new(u64::from(day.number));
+        let input = (day.generate)(&mut rng, 20)
+            .unwrap_or_else(|| panic!("Day {} has no generator", day.number));
+        let path = std::env::temp_dir().join(format!("rustvent-generated-{:02}.txt", day.number));
+        std::fs::write(&path, input).unwrap();
+        let parsed = day.parse_named(path.to_str().unwrap()).unwrap();
+        parsed.part(1);
+        parsed.part(2);
+    }
+}
@@ -0,0 +1,15 @@
+use rustvent_2022::{days, fuzz};
+
+/// A short run of the hand-written parsers, `fuzz --day <DAY>` runs longer ones.
+#[test]
+fn test_fuzz_parsers() {
+    for day in [5, 11, 13, 22] {
+        let day = days::find(day).unwrap();
+        if let Err(failure) = fuzz::fuzz(day, 200, 0) {
+            panic!(
+                "Day {}: {}, input:\n{}",
+                day.number, failure.problem, failure.input
+            );
+        }
+    }
+}